@@ -0,0 +1,287 @@
+use crate::{
+    ast::{Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind},
+    interning::InternedStr,
+    lexer::{Lexer, LexerOptions, Location, TokenKind},
+    lints::{check_brace_indent, check_dead_functions, check_leading_zeros},
+    resolve::check_global_names,
+    visitor::{walk_expression, Visitor},
+};
+use derive_more::derive::Display;
+use rustc_hash::FxHashSet;
+use std::convert::Infallible;
+
+/// A message from any of the passes run by `analyze`
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+#[display("{location}: {message}")]
+pub struct Diagnostic {
+    /// The name of the error or warning variant, which stays the same when the message is reworded
+    pub code: &'static str,
+    pub message: String,
+    pub location: Location,
+    /// The end of the token at `location`, or `location` itself when there is no token there
+    pub end: Location,
+}
+
+impl Diagnostic {
+    fn new(code: &'static str, message: impl ToString, location: Location, source: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            location,
+            end: token_end(source, location),
+        }
+    }
+}
+
+fn token_end(source: &str, location: Location) -> Location {
+    if !source.is_char_boundary(location.position) {
+        return location;
+    }
+    let options = LexerOptions::default();
+    let mut lexer = Lexer::new_at(source, location, &options);
+    match lexer.next_raw_token() {
+        Ok(_) => lexer.location(),
+        Err(_) => location,
+    }
+}
+
+/// The diagnostics of every pass, each sorted by file and then position
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Runs name resolution and all lints over the already parsed `asts` of `source`, dead functions are the ones
+/// that cannot be reached from `main`
+pub fn analyze(filepath: InternedStr, source: &str, asts: &[Ast]) -> Analysis {
+    let mut analysis = Analysis::default();
+
+    if let Err(errors) = check_global_names(asts) {
+        analysis.errors.extend(
+            errors.into_iter().map(|error| {
+                Diagnostic::new(error.kind.code(), error.kind, error.location, source)
+            }),
+        );
+    }
+
+    for lint in [check_brace_indent, check_leading_zeros] {
+        match lint(filepath, source) {
+            Ok(warnings) => analysis
+                .warnings
+                .extend(warnings.into_iter().map(|warning| {
+                    Diagnostic::new(warning.kind.code(), warning.kind, warning.location, source)
+                })),
+            Err(error) => {
+                analysis.errors.push(Diagnostic::new(
+                    error.kind.code(),
+                    error.kind,
+                    error.location,
+                    source,
+                ));
+                break;
+            }
+        }
+    }
+
+    analysis.warnings.extend(
+        check_dead_functions(asts, &[InternedStr::from("main")])
+            .into_iter()
+            .map(|warning| {
+                Diagnostic::new(warning.kind.code(), warning.kind, warning.location, source)
+            }),
+    );
+
+    for diagnostics in [&mut analysis.errors, &mut analysis.warnings] {
+        diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.location.filepath, diagnostic.location.position));
+    }
+    analysis
+}
+
+/// The names referenced in `expression` that are not bound by a `let`, function or closure argument inside it
+///
+/// Type annotations are not values, so names only used as types are not included
+pub fn free_variables(expression: &AstExpression) -> FxHashSet<InternedStr> {
+    let mut free_variables = FreeVariables::default();
+    let Ok(()) = free_variables.visit_expression(expression);
+    free_variables.free
+}
+
+/// The names that are called directly in `ast` without being bound inside it, like `foo` in `foo(1)`
+pub fn called_names(ast: &Ast) -> FxHashSet<InternedStr> {
+    let mut free_variables = FreeVariables::default();
+    let Ok(()) = free_variables.visit_ast(ast);
+    free_variables.called
+}
+
+#[derive(Default)]
+struct FreeVariables {
+    scopes: Vec<FxHashSet<InternedStr>>,
+    free: FxHashSet<InternedStr>,
+    /// The free names that are the operand of a call
+    called: FxHashSet<InternedStr>,
+}
+
+impl FreeVariables {
+    fn bind(&mut self, name: InternedStr) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name);
+        }
+    }
+
+    fn is_bound(&self, name: InternedStr) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(&name))
+    }
+
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(FxHashSet::default());
+        f(self);
+        self.scopes.pop();
+    }
+}
+
+impl Visitor for FreeVariables {
+    type Error = Infallible;
+
+    fn visit_ast(&mut self, ast: &Ast) -> Result<(), Infallible> {
+        match ast.kind {
+            AstKind::Let {
+                ref pattern,
+                equals: _,
+                ref value,
+            } => {
+                // the value is evaluated before the pattern's names come into scope
+                self.visit_expression(value)?;
+                self.visit_pattern(pattern)?;
+            }
+            AstKind::Function {
+                doc: _,
+                attributes: _,
+                ref name,
+                generics: _,
+                ref arguments,
+                return_type: _,
+                where_clause: _,
+                ref body,
+            } => {
+                let TokenKind::Name(name) = name.kind else {
+                    unreachable!();
+                };
+                self.bind(name);
+                self.scoped(|this| {
+                    for argument in arguments {
+                        let Ok(()) = this.visit_pattern(argument);
+                    }
+                    let Ok(()) = this.visit_expression(body);
+                });
+            }
+            AstKind::Expression(ref expression) | AstKind::Return { ref expression } => {
+                self.visit_expression(expression)?;
+            }
+            AstKind::Assignment {
+                ref target,
+                equals: _,
+                ref value,
+            } => {
+                self.visit_expression(target)?;
+                self.visit_expression(value)?;
+            }
+            AstKind::For {
+                ref pattern,
+                ref iterable,
+                ref body,
+            } => {
+                self.visit_expression(iterable)?;
+                self.scoped(|this| {
+                    let Ok(()) = this.visit_pattern(pattern);
+                    let Ok(()) = this.visit_expression(body);
+                });
+            }
+            // a struct's name is a type and its fields are types, neither of which are values
+            AstKind::Struct { .. } => {}
+            // the same goes for an enum and the payloads of its variants
+            AstKind::Enum { .. } => {}
+            AstKind::ExternFunction { ref name, .. } => {
+                let TokenKind::Name(name) = name.kind else {
+                    unreachable!();
+                };
+                self.bind(name);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_expression(&mut self, expression: &AstExpression) -> Result<(), Infallible> {
+        match expression.kind {
+            AstExpressionKind::Name(name) => {
+                if !self.is_bound(name) {
+                    self.free.insert(name);
+                }
+            }
+            AstExpressionKind::Call { ref operand, .. } => {
+                if let AstExpressionKind::Name(name) = operand.kind {
+                    if !self.is_bound(name) {
+                        self.called.insert(name);
+                    }
+                }
+                walk_expression(self, expression)?;
+            }
+            AstExpressionKind::Block { .. } | AstExpressionKind::Closure { .. } => {
+                self.scoped(|this| {
+                    let Ok(()) = walk_expression(this, expression);
+                });
+            }
+            AstExpressionKind::Match {
+                ref scrutinee,
+                ref arms,
+                close_brace: _,
+            } => {
+                self.visit_expression(scrutinee)?;
+                // each arm's pattern only binds names in that arm
+                for (pattern, value) in arms {
+                    self.scoped(|this| {
+                        let Ok(()) = this.visit_pattern(pattern);
+                        let Ok(()) = this.visit_expression(value);
+                    });
+                }
+            }
+            _ => walk_expression(self, expression)?,
+        }
+        Ok(())
+    }
+
+    fn visit_pattern(&mut self, pattern: &AstPattern) -> Result<(), Infallible> {
+        match pattern.kind {
+            AstPatternKind::Let {
+                ref name_token,
+                typ: _,
+            } => {
+                let TokenKind::Name(name) = name_token.kind else {
+                    unreachable!();
+                };
+                self.bind(name);
+            }
+            AstPatternKind::Wildcard
+            | AstPatternKind::Integer(_)
+            | AstPatternKind::Range { .. }
+            | AstPatternKind::Boolean(_) => {}
+            AstPatternKind::Tuple(ref elements) => {
+                for element in elements {
+                    self.visit_pattern(element)?;
+                }
+            }
+            AstPatternKind::Binding {
+                ref name,
+                ref pattern,
+            } => {
+                let TokenKind::Name(name) = name.kind else {
+                    unreachable!();
+                };
+                self.bind(name);
+                self.visit_pattern(pattern)?;
+            }
+        }
+        Ok(())
+    }
+}
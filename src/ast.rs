@@ -3,9 +3,10 @@ use crate::{
     lexer::{Location, Token, TokenKind},
 };
 use derive_more::derive::Display;
-use std::num::NonZero;
+use rustc_hash::FxHashMap;
+use std::{num::NonZero, ops::Range};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AstKind {
     Expression(AstExpression),
     Let {
@@ -14,22 +15,96 @@ pub enum AstKind {
         value: Box<AstExpression>,
     },
     Function {
+        /// The text of the `/** ... */` comments before the function
+        doc: Option<InternedStr>,
+        attributes: Vec<AstAttribute>,
         name: Token,
+        /// The names of the `<T, ...>` type parameters, which are parsed but not yet checked
+        generics: Vec<Token>,
         arguments: Vec<AstPattern>,
         return_type: Option<Box<AstExpression>>,
+        /// The `T: Bound` constraints of a `where` clause, which are parsed but not yet checked
+        where_clause: Vec<(Token, AstExpression)>,
         body: AstExpression,
     },
     Return {
         expression: AstExpression,
     },
+    /// `extern fn name(arguments) -> return_type;`, a function provided by the host instead of having a body
+    ExternFunction {
+        doc: Option<InternedStr>,
+        attributes: Vec<AstAttribute>,
+        name: Token,
+        arguments: Vec<AstPattern>,
+        return_type: Option<Box<AstExpression>>,
+    },
+    /// `struct Name { field: type, ... }`
+    Struct {
+        doc: Option<InternedStr>,
+        attributes: Vec<AstAttribute>,
+        name: Token,
+        fields: Vec<(Token, AstExpression)>,
+    },
+    /// `enum Name { Variant, Variant(type, ...), ... }`, where a variant without a payload has no types
+    Enum {
+        doc: Option<InternedStr>,
+        attributes: Vec<AstAttribute>,
+        name: Token,
+        variants: Vec<(Token, Vec<AstExpression>)>,
+    },
+    /// `for pattern in iterable { ... }`
+    For {
+        pattern: AstPattern,
+        iterable: Box<AstExpression>,
+        body: Box<AstExpression>,
+    },
+    /// `target = value;`, where the target is always a place expression
+    Assignment {
+        target: AstExpression,
+        equals: Location,
+        value: Box<AstExpression>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Ast {
     pub kind: AstKind,
     pub location: Location,
 }
 
+/// An attribute like `#[name]` or `#[name(arguments)]` attached to an item
+#[derive(Debug, Clone, PartialEq)]
+pub struct AstAttribute {
+    pub name: Token,
+    pub arguments: Option<Vec<AstExpression>>,
+    pub location: Location,
+}
+
+/// The items of several files, kept in the order the files were given
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub items: Vec<Ast>,
+    pub files: FxHashMap<InternedStr, Range<usize>>,
+    /// The `//!` lines at the start of each file, one line each with the files separated by an empty line
+    pub module_doc: Option<InternedStr>,
+}
+
+impl Module {
+    pub fn items_in(&self, filepath: InternedStr) -> &[Ast] {
+        self.files
+            .get(&filepath)
+            .map_or(&[], |range| &self.items[range.clone()])
+    }
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    #[display("left")]
+    Left,
+    #[display("right")]
+    Right,
+}
+
 #[derive(Debug, Display, Clone, PartialEq, Eq)]
 pub enum BinaryOperator {
     #[display("+")]
@@ -40,6 +115,24 @@ pub enum BinaryOperator {
     Multiply,
     #[display("/")]
     Divide,
+    #[display("%")]
+    Modulo,
+    #[display("==")]
+    Equal,
+    #[display("!=")]
+    NotEqual,
+    #[display("<")]
+    Less,
+    #[display(">")]
+    Greater,
+    #[display("<=")]
+    LessEqual,
+    #[display(">=")]
+    GreaterEqual,
+    #[display("&&")]
+    And,
+    #[display("||")]
+    Or,
 }
 
 impl BinaryOperator {
@@ -49,6 +142,15 @@ impl BinaryOperator {
             TokenKind::Minus => BinaryOperator::Subtract,
             TokenKind::Asterisk => BinaryOperator::Multiply,
             TokenKind::Slash => BinaryOperator::Divide,
+            TokenKind::Percent => BinaryOperator::Modulo,
+            TokenKind::EqualsEquals => BinaryOperator::Equal,
+            TokenKind::BangEquals => BinaryOperator::NotEqual,
+            TokenKind::Less => BinaryOperator::Less,
+            TokenKind::Greater => BinaryOperator::Greater,
+            TokenKind::LessEquals => BinaryOperator::LessEqual,
+            TokenKind::GreaterEquals => BinaryOperator::GreaterEqual,
+            TokenKind::AmpersandAmpersand => BinaryOperator::And,
+            TokenKind::PipePipe => BinaryOperator::Or,
             _ => return None,
         })
     }
@@ -66,47 +168,282 @@ impl BinaryOperator {
         }
 
         match *self {
-            BinaryOperator::Multiply | BinaryOperator::Divide => l!(2),
-            BinaryOperator::Add | BinaryOperator::Subtract => l!(1),
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => l!(5),
+            BinaryOperator::Add | BinaryOperator::Subtract => l!(4),
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::Greater
+            | BinaryOperator::LessEqual
+            | BinaryOperator::GreaterEqual => l!(3),
+            BinaryOperator::And => l!(2),
+            BinaryOperator::Or => l!(1),
+        }
+    }
+
+    pub fn associativity(&self) -> Associativity {
+        match *self {
+            BinaryOperator::Add
+            | BinaryOperator::Subtract
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo
+            | BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::Greater
+            | BinaryOperator::LessEqual
+            | BinaryOperator::GreaterEqual
+            | BinaryOperator::And
+            | BinaryOperator::Or => Associativity::Left,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum UnaryOperator {
+    #[display("-")]
+    Negate,
+    #[display("!")]
+    Not,
+}
+
+impl UnaryOperator {
+    pub fn from_token_kind(kind: TokenKind) -> Option<UnaryOperator> {
+        Some(match kind {
+            TokenKind::Minus => UnaryOperator::Negate,
+            TokenKind::Bang => UnaryOperator::Not,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AstExpressionKind {
     Name(InternedStr),
     Integer(u64),
+    Float(f64),
+    String(InternedStr),
+    Char(char),
+    Boolean(bool),
+    /// `()`, the value of expressions that have no other value
+    Unit,
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<AstExpression>,
+    },
     Binary {
         left: Box<AstExpression>,
         operator: BinaryOperator,
         right: Box<AstExpression>,
     },
+    /// `start..end` or `start..=end`, where either bound can be left out of an exclusive range
+    Range {
+        start: Option<Box<AstExpression>>,
+        end: Option<Box<AstExpression>>,
+        inclusive: bool,
+    },
+    /// `[a, b, c]`
+    Array {
+        elements: Vec<AstExpression>,
+        close_bracket: Location,
+    },
+    /// `[value; count]`
+    ArrayRepeat {
+        value: Box<AstExpression>,
+        count: Box<AstExpression>,
+        close_bracket: Location,
+    },
     Block {
         statements: Vec<Ast>,
         close_brace: Location,
     },
+    /// `if condition { ... } else { ... }`, where the else block is another `If` for `else if`
+    If {
+        condition: Box<AstExpression>,
+        then_block: Box<AstExpression>,
+        else_block: Option<Box<AstExpression>>,
+    },
+    /// `match scrutinee { pattern => expression, ... }`
+    Match {
+        scrutinee: Box<AstExpression>,
+        arms: Vec<(AstPattern, AstExpression)>,
+        close_brace: Location,
+    },
     Call {
         operand: Box<AstExpression>,
         arguments: Vec<AstExpression>,
+        /// The location of every comma between the arguments
+        commas: Vec<Location>,
+        close_parenthesis: Location,
+    },
+    /// `Name { field: value, ... }`
+    StructLiteral {
+        name: Token,
+        fields: Vec<(Token, AstExpression)>,
+        close_brace: Location,
+    },
+    /// `operand.name`
+    FieldAccess {
+        operand: Box<AstExpression>,
+        name_token: Token,
+    },
+    /// `operand[index]`
+    Index {
+        operand: Box<AstExpression>,
+        index: Box<AstExpression>,
+        close_bracket: Location,
+    },
+    Closure {
+        arguments: Vec<AstPattern>,
+        statements: Vec<Ast>,
+        close_brace: Location,
+    },
+    /// Explicit parentheses, only kept when parsing with `ParserOptions::preserve_parens`
+    Paren {
+        inner: Box<AstExpression>,
         close_parenthesis: Location,
     },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AstExpression {
     pub kind: AstExpressionKind,
     pub location: Location,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl AstExpression {
+    /// Whether this expression ends in a block, so it needs no `;` after it at the start of a statement
+    pub fn is_block_like(&self) -> bool {
+        matches!(
+            self.kind,
+            AstExpressionKind::Block { .. }
+                | AstExpressionKind::If { .. }
+                | AstExpressionKind::Match { .. }
+        )
+    }
+
+    /// Whether this expression denotes a memory location that can be assigned to
+    pub fn is_place_expression(&self) -> bool {
+        match self.kind {
+            AstExpressionKind::Name(_)
+            | AstExpressionKind::FieldAccess { .. }
+            | AstExpressionKind::Index { .. } => true,
+            AstExpressionKind::Paren { ref inner, .. } => inner.is_place_expression(),
+            AstExpressionKind::Integer(_)
+            | AstExpressionKind::Float(_)
+            | AstExpressionKind::String(_)
+            | AstExpressionKind::Char(_)
+            | AstExpressionKind::Boolean(_)
+            | AstExpressionKind::Unit
+            | AstExpressionKind::Unary { .. }
+            | AstExpressionKind::Binary { .. }
+            | AstExpressionKind::Range { .. }
+            | AstExpressionKind::Array { .. }
+            | AstExpressionKind::ArrayRepeat { .. }
+            | AstExpressionKind::Block { .. }
+            | AstExpressionKind::If { .. }
+            | AstExpressionKind::Match { .. }
+            | AstExpressionKind::Call { .. }
+            | AstExpressionKind::StructLiteral { .. }
+            | AstExpressionKind::Closure { .. } => false,
+        }
+    }
+
+    /// The index of the argument a cursor at byte `position` is in, if this is a call and the position is inside its parentheses or trailing closure
+    pub fn argument_at(&self, position: usize) -> Option<usize> {
+        let AstExpressionKind::Call {
+            operand: _,
+            ref arguments,
+            ref commas,
+            close_parenthesis,
+        } = self.kind
+        else {
+            return None;
+        };
+
+        if position > self.location.position && position <= close_parenthesis.position {
+            return Some(
+                commas
+                    .iter()
+                    .take_while(|comma| comma.position < position)
+                    .count(),
+            );
+        }
+
+        // a trailing closure comes after the parentheses, but is still the last argument
+        match arguments.last() {
+            Some(&AstExpression {
+                kind: AstExpressionKind::Closure { close_brace, .. },
+                location,
+            }) if location.position > close_parenthesis.position
+                && position > location.position
+                && position <= close_brace.position =>
+            {
+                Some(arguments.len() - 1)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A chain like `a + b + c + ...` nests once per operator, so the operands of a chain are dropped from a list
+/// instead of recursively, as a long enough chain would overflow the stack
+impl Drop for AstExpression {
+    fn drop(&mut self) {
+        let mut operands = vec![];
+        take_operands(&mut self.kind, &mut operands);
+        while let Some(operand) = operands.last_mut() {
+            let mut kind = std::mem::replace(&mut operand.kind, AstExpressionKind::Unit);
+            operands.pop();
+            take_operands(&mut kind, &mut operands);
+        }
+    }
+}
+
+fn take_operands(kind: &mut AstExpressionKind, operands: &mut Vec<AstExpression>) {
+    if !matches!(
+        kind,
+        AstExpressionKind::Unary { .. }
+            | AstExpressionKind::Binary { .. }
+            | AstExpressionKind::Paren { .. }
+    ) {
+        return;
+    }
+    match std::mem::replace(kind, AstExpressionKind::Unit) {
+        AstExpressionKind::Unary { operand, .. } => operands.push(*operand),
+        AstExpressionKind::Binary { left, right, .. } => operands.extend([*left, *right]),
+        AstExpressionKind::Paren { inner, .. } => operands.push(*inner),
+        _ => unreachable!(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AstPatternKind {
     Let {
         name_token: Token,
         typ: Option<AstExpression>,
     },
+    /// `_`, which matches anything without binding it
+    Wildcard,
+    Integer(u64),
+    /// `start..end` or `start..=end` over integer literals
+    Range {
+        start: u64,
+        end: u64,
+        inclusive: bool,
+    },
+    Boolean(bool),
+    /// `(a, b, ...)`, where a single element needs a trailing comma like `(a,)` to not be parentheses
+    Tuple(Vec<AstPattern>),
+    /// `name @ pattern`, which binds the whole value to `name` as well as matching it against `pattern`
+    Binding {
+        name: Token,
+        pattern: Box<AstPattern>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AstPattern {
     pub kind: AstPatternKind,
     pub location: Location,
@@ -1,6 +1,11 @@
-use lang::{parsing::parse, pretty_printing::pretty_print_ast};
+use lang::{
+    analysis::analyze, json::write_diagnostics_jsonl, parsing::parse,
+    pretty_printing::pretty_print_ast,
+};
 
 fn main() {
+    let explain = std::env::args().any(|argument| argument == "--explain");
+    let jsonl = std::env::args().any(|argument| argument == "--jsonl");
     let filepath = "test.lang";
     let source = "
 fn foo(param) -> int {
@@ -14,9 +19,32 @@ fn foo(param) -> int {
 }
 ";
     let asts = parse(filepath.into(), source).unwrap_or_else(|error| {
+        #[cfg(feature = "fancy-errors")]
+        error
+            .report(source)
+            .eprint((filepath, ariadne::Source::from(source)))
+            .unwrap();
+        #[cfg(not(feature = "fancy-errors"))]
         eprintln!("{error}");
+        if explain {
+            eprintln!("\n{}", error.kind.explanation());
+        }
         std::process::exit(1)
     });
+    let analysis = analyze(filepath.into(), source, &asts);
+    if jsonl {
+        write_diagnostics_jsonl(&analysis, &mut std::io::stderr()).unwrap();
+    } else {
+        for warning in &analysis.warnings {
+            eprintln!("warning: {warning}");
+        }
+        for error in &analysis.errors {
+            eprintln!("error: {error}");
+        }
+    }
+    if !analysis.errors.is_empty() {
+        std::process::exit(1)
+    }
     let stdout = &mut std::io::stdout();
     for ast in asts {
         pretty_print_ast(&ast, 0, stdout).unwrap();
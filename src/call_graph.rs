@@ -0,0 +1,51 @@
+use crate::{
+    analysis::called_names,
+    ast::{Ast, AstKind},
+    resolve::{global_declarations, DeclId},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// The functions each global function calls directly, only calls of a global function's name are included
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    pub calls: FxHashMap<DeclId, FxHashSet<DeclId>>,
+}
+
+impl CallGraph {
+    pub fn callees(&self, caller: DeclId) -> impl Iterator<Item = DeclId> + '_ {
+        self.calls.get(&caller).into_iter().flatten().copied()
+    }
+
+    /// Every function that can be called starting from `entry`, including `entry` itself
+    pub fn reachable_from(&self, entry: DeclId) -> FxHashSet<DeclId> {
+        let mut reachable = FxHashSet::from_iter([entry]);
+        let mut stack = vec![entry];
+        while let Some(caller) = stack.pop() {
+            for callee in self.callees(caller) {
+                if reachable.insert(callee) {
+                    stack.push(callee);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+/// Builds the call graph of the global items in `asts`, resolving each called name to the global it refers to
+pub fn call_graph(asts: &[Ast]) -> CallGraph {
+    let declarations = global_declarations(asts);
+    let calls = declarations
+        .values()
+        .map(|&id| {
+            let callees = match asts[id.0].kind {
+                AstKind::Function { .. } => called_names(&asts[id.0])
+                    .into_iter()
+                    .filter_map(|name| declarations.get(&name).copied())
+                    .collect(),
+                _ => FxHashSet::default(),
+            };
+            (id, callees)
+        })
+        .collect();
+    CallGraph { calls }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    ast::{Ast, AstExpression, AstExpressionKind, AstKind},
+    interning::InternedStr,
+    lexer::TokenKind,
+};
+use std::{collections::HashSet, hash::BuildHasher};
+
+/// Removes the top level items that have a `#[cfg(predicate)]` attribute which is not satisfied by `active`
+///
+/// A predicate is either a feature name, or `not(predicate)`, `all(predicates...)` or `any(predicates...)`,
+/// anything else is never satisfied
+pub fn filter_cfg<S: BuildHasher>(asts: Vec<Ast>, active: &HashSet<InternedStr, S>) -> Vec<Ast> {
+    asts.into_iter()
+        .filter(|ast| is_cfg_enabled(ast, active))
+        .collect()
+}
+
+pub fn is_cfg_enabled<S: BuildHasher>(ast: &Ast, active: &HashSet<InternedStr, S>) -> bool {
+    let attributes = match ast.kind {
+        AstKind::Function { ref attributes, .. }
+        | AstKind::ExternFunction { ref attributes, .. }
+        | AstKind::Struct { ref attributes, .. }
+        | AstKind::Enum { ref attributes, .. } => attributes,
+        AstKind::Expression(_)
+        | AstKind::Let { .. }
+        | AstKind::Return { .. }
+        | AstKind::For { .. }
+        | AstKind::Assignment { .. } => return true,
+    };
+
+    attributes
+        .iter()
+        .filter(|attribute| matches!(attribute.name.kind, TokenKind::Name(name) if name.to_str() == "cfg"))
+        .all(|attribute| match attribute.arguments.as_deref() {
+            Some([predicate]) => evaluate_predicate(predicate, active),
+            _ => false,
+        })
+}
+
+fn evaluate_predicate<S: BuildHasher>(
+    predicate: &AstExpression,
+    active: &HashSet<InternedStr, S>,
+) -> bool {
+    match predicate.kind {
+        AstExpressionKind::Name(feature) => active.contains(&feature),
+        AstExpressionKind::Call {
+            ref operand,
+            ref arguments,
+            commas: _,
+            close_parenthesis: _,
+        } => {
+            let AstExpressionKind::Name(name) = operand.kind else {
+                return false;
+            };
+            match (name.to_str(), &arguments[..]) {
+                ("not", [predicate]) => !evaluate_predicate(predicate, active),
+                ("all", predicates) => predicates
+                    .iter()
+                    .all(|predicate| evaluate_predicate(predicate, active)),
+                ("any", predicates) => predicates
+                    .iter()
+                    .any(|predicate| evaluate_predicate(predicate, active)),
+                _ => false,
+            }
+        }
+        AstExpressionKind::Paren {
+            ref inner,
+            close_parenthesis: _,
+        } => evaluate_predicate(inner, active),
+        _ => false,
+    }
+}
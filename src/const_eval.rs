@@ -0,0 +1,98 @@
+use crate::{
+    ast::{AstExpression, AstExpressionKind},
+    interning::InternedStr,
+    interpreter::{RuntimeErrorKind, Value},
+    lexer::Location,
+};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+/// Constants are evaluated with the same values and arithmetic as the interpreter
+pub type ConstValue = Value;
+
+/// The values of the constants that a constant expression can refer to
+pub type ConstEnv = FxHashMap<InternedStr, ConstValue>;
+
+#[derive(Debug, Error)]
+pub enum ConstEvalErrorKind {
+    #[error("This expression cannot be evaluated at compile time")]
+    NotConstant,
+    #[error("'{0}' is not a constant")]
+    NotAConstant(InternedStr),
+    #[error("{0}")]
+    Arithmetic(RuntimeErrorKind),
+}
+
+#[derive(Debug, Error)]
+#[error("{location}: {kind}")]
+pub struct ConstEvalError {
+    pub kind: ConstEvalErrorKind,
+    pub location: Location,
+}
+
+/// Evaluates the pure subset of expressions, integer, boolean and unit literals, operators and names of other constants
+pub fn eval_const(
+    expression: &AstExpression,
+    consts: &ConstEnv,
+) -> Result<ConstValue, ConstEvalError> {
+    let error = |kind| ConstEvalError {
+        kind,
+        location: expression.location,
+    };
+    Ok(match expression.kind {
+        AstExpressionKind::Name(name) => *consts
+            .get(&name)
+            .ok_or_else(|| error(ConstEvalErrorKind::NotAConstant(name)))?,
+        AstExpressionKind::Integer(value) => Value::Integer(value.try_into().map_err(|_| {
+            error(ConstEvalErrorKind::Arithmetic(
+                RuntimeErrorKind::IntegerOverflow,
+            ))
+        })?),
+        AstExpressionKind::Boolean(value) => Value::Bool(value),
+        AstExpressionKind::Unit => Value::Unit,
+        AstExpressionKind::Unary {
+            ref operator,
+            ref operand,
+        } if operator.is_min_integer(operand) => Value::Integer(i64::MIN),
+        AstExpressionKind::Unary {
+            ref operator,
+            ref operand,
+        } => operator
+            .apply(eval_const(operand, consts)?)
+            .map_err(|kind| error(ConstEvalErrorKind::Arithmetic(kind)))?,
+        AstExpressionKind::Binary {
+            ref left,
+            ref operator,
+            ref right,
+        } => {
+            let left = eval_const(left, consts)?;
+            if let Some(value) = operator.short_circuit(left) {
+                return Ok(value);
+            }
+            let right = eval_const(right, consts)?;
+            operator
+                .apply(left, right)
+                .map_err(|kind| error(ConstEvalErrorKind::Arithmetic(kind)))?
+        }
+        AstExpressionKind::Paren {
+            ref inner,
+            close_parenthesis: _,
+        } => eval_const(inner, consts)?,
+        AstExpressionKind::Float(_)
+        | AstExpressionKind::String(_)
+        | AstExpressionKind::Char(_)
+        | AstExpressionKind::Range { .. }
+        | AstExpressionKind::Array { .. }
+        | AstExpressionKind::ArrayRepeat { .. }
+        | AstExpressionKind::Block { .. }
+        | AstExpressionKind::If { .. }
+        | AstExpressionKind::Match { .. }
+        | AstExpressionKind::Call { .. }
+        | AstExpressionKind::StructLiteral { .. }
+        | AstExpressionKind::FieldAccess { .. }
+        | AstExpressionKind::Index { .. }
+        | AstExpressionKind::Closure { .. } => {
+            return Err(error(ConstEvalErrorKind::NotConstant));
+        }
+    })
+}
@@ -0,0 +1,682 @@
+use crate::{
+    ast::{
+        Ast, AstAttribute, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind,
+    },
+    interning::InternedStr,
+    lexer::Token,
+};
+use derive_more::derive::Display;
+
+/// A place where two trees diverge, like `function body → statement[2] → binary right operand: Integer(3) vs Integer(4)`
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+#[display("{}: {left} vs {right}", path.join(" → "))]
+pub struct AstDiff {
+    pub path: Vec<String>,
+    pub left: String,
+    pub right: String,
+}
+
+/// The divergences between two trees, locations are ignored and nodes of different kinds are not descended into
+pub fn ast_diff(a: &Ast, b: &Ast) -> Vec<AstDiff> {
+    let mut differ = Differ::default();
+    differ.ast(a, b);
+    differ.diffs
+}
+
+pub fn expression_diff(a: &AstExpression, b: &AstExpression) -> Vec<AstDiff> {
+    let mut differ = Differ::default();
+    differ.expression(a, b);
+    differ.diffs
+}
+
+/// Whether the trees are equal when locations are ignored
+pub fn structurally_eq(a: &Ast, b: &Ast) -> bool {
+    ast_diff(a, b).is_empty()
+}
+
+#[derive(Default)]
+struct Differ {
+    path: Vec<String>,
+    diffs: Vec<AstDiff>,
+}
+
+impl Differ {
+    fn report(&mut self, left: impl ToString, right: impl ToString) {
+        self.diffs.push(AstDiff {
+            path: self.path.clone(),
+            left: left.to_string(),
+            right: right.to_string(),
+        });
+    }
+
+    fn at(&mut self, step: impl Into<String>, f: impl FnOnce(&mut Self)) {
+        self.path.push(step.into());
+        f(self);
+        self.path.pop();
+    }
+
+    fn list<T>(&mut self, name: &str, a: &[T], b: &[T], f: impl Fn(&mut Self, &T, &T)) {
+        for (i, (a, b)) in a.iter().zip(b).enumerate() {
+            self.at(format!("{name}[{i}]"), |this| f(this, a, b));
+        }
+        if a.len() != b.len() {
+            self.report(
+                format!("{} {name}s", a.len()),
+                format!("{} {name}s", b.len()),
+            );
+        }
+    }
+
+    fn token(&mut self, a: &Token, b: &Token) {
+        if a.kind != b.kind {
+            self.report(&a.kind, &b.kind);
+        }
+    }
+
+    fn optional_expression(&mut self, a: Option<&AstExpression>, b: Option<&AstExpression>) {
+        match (a, b) {
+            (Some(a), Some(b)) => self.expression(a, b),
+            (None, None) => {}
+            (a, b) => self.report(
+                a.map_or("nothing".into(), describe_expression),
+                b.map_or("nothing".into(), describe_expression),
+            ),
+        }
+    }
+
+    fn ast(&mut self, a: &Ast, b: &Ast) {
+        match (&a.kind, &b.kind) {
+            (AstKind::Expression(a), AstKind::Expression(b)) => self.expression(a, b),
+            (
+                AstKind::Let {
+                    pattern: a_pattern,
+                    equals: _,
+                    value: a_value,
+                },
+                AstKind::Let {
+                    pattern: b_pattern,
+                    equals: _,
+                    value: b_value,
+                },
+            ) => {
+                self.at("let pattern", |this| this.pattern(a_pattern, b_pattern));
+                self.at("let value", |this| this.expression(a_value, b_value));
+            }
+            (
+                AstKind::Function {
+                    doc: a_doc,
+                    attributes: a_attributes,
+                    name: a_name,
+                    generics: a_generics,
+                    arguments: a_arguments,
+                    return_type: a_return_type,
+                    where_clause: a_where_clause,
+                    body: a_body,
+                },
+                AstKind::Function {
+                    doc: b_doc,
+                    attributes: b_attributes,
+                    name: b_name,
+                    generics: b_generics,
+                    arguments: b_arguments,
+                    return_type: b_return_type,
+                    where_clause: b_where_clause,
+                    body: b_body,
+                },
+            ) => {
+                self.doc("function doc", a_doc, b_doc);
+                self.list("attribute", a_attributes, b_attributes, Self::attribute);
+                self.at("function name", |this| this.token(a_name, b_name));
+                self.list("type parameter", a_generics, b_generics, Self::token);
+                self.list("argument", a_arguments, b_arguments, Self::pattern);
+                self.at("function return type", |this| {
+                    this.optional_expression(a_return_type.as_deref(), b_return_type.as_deref());
+                });
+                self.list(
+                    "constraint",
+                    a_where_clause,
+                    b_where_clause,
+                    |this, (a_typ, a_bound), (b_typ, b_bound)| {
+                        this.at("constrained type", |this| this.token(a_typ, b_typ));
+                        this.at("constraint bound", |this| this.expression(a_bound, b_bound));
+                    },
+                );
+                self.at("function body", |this| this.expression(a_body, b_body));
+            }
+            (
+                AstKind::ExternFunction {
+                    doc: a_doc,
+                    attributes: a_attributes,
+                    name: a_name,
+                    arguments: a_arguments,
+                    return_type: a_return_type,
+                },
+                AstKind::ExternFunction {
+                    doc: b_doc,
+                    attributes: b_attributes,
+                    name: b_name,
+                    arguments: b_arguments,
+                    return_type: b_return_type,
+                },
+            ) => {
+                self.doc("function doc", a_doc, b_doc);
+                self.list("attribute", a_attributes, b_attributes, Self::attribute);
+                self.at("function name", |this| this.token(a_name, b_name));
+                self.list("argument", a_arguments, b_arguments, Self::pattern);
+                self.at("function return type", |this| {
+                    this.optional_expression(a_return_type.as_deref(), b_return_type.as_deref());
+                });
+            }
+            (
+                AstKind::Struct {
+                    doc: a_doc,
+                    attributes: a_attributes,
+                    name: a_name,
+                    fields: a_fields,
+                },
+                AstKind::Struct {
+                    doc: b_doc,
+                    attributes: b_attributes,
+                    name: b_name,
+                    fields: b_fields,
+                },
+            ) => {
+                self.doc("struct doc", a_doc, b_doc);
+                self.list("attribute", a_attributes, b_attributes, Self::attribute);
+                self.at("struct name", |this| this.token(a_name, b_name));
+                self.list("field", a_fields, b_fields, |this, a, b| {
+                    this.at("field name", |this| this.token(&a.0, &b.0));
+                    this.at("field type", |this| this.expression(&a.1, &b.1));
+                });
+            }
+            (
+                AstKind::Enum {
+                    doc: a_doc,
+                    attributes: a_attributes,
+                    name: a_name,
+                    variants: a_variants,
+                },
+                AstKind::Enum {
+                    doc: b_doc,
+                    attributes: b_attributes,
+                    name: b_name,
+                    variants: b_variants,
+                },
+            ) => {
+                self.doc("enum doc", a_doc, b_doc);
+                self.list("attribute", a_attributes, b_attributes, Self::attribute);
+                self.at("enum name", |this| this.token(a_name, b_name));
+                self.list("variant", a_variants, b_variants, |this, a, b| {
+                    this.at("variant name", |this| this.token(&a.0, &b.0));
+                    this.list("payload type", &a.1, &b.1, |this, a, b| {
+                        this.expression(a, b)
+                    });
+                });
+            }
+            (
+                AstKind::For {
+                    pattern: a_pattern,
+                    iterable: a_iterable,
+                    body: a_body,
+                },
+                AstKind::For {
+                    pattern: b_pattern,
+                    iterable: b_iterable,
+                    body: b_body,
+                },
+            ) => {
+                self.at("for pattern", |this| this.pattern(a_pattern, b_pattern));
+                self.at("for iterable", |this| {
+                    this.expression(a_iterable, b_iterable)
+                });
+                self.at("for body", |this| this.expression(a_body, b_body));
+            }
+            (AstKind::Return { expression: a }, AstKind::Return { expression: b }) => {
+                self.at("return value", |this| this.expression(a, b));
+            }
+            (
+                AstKind::Assignment {
+                    target: a_target,
+                    equals: _,
+                    value: a_value,
+                },
+                AstKind::Assignment {
+                    target: b_target,
+                    equals: _,
+                    value: b_value,
+                },
+            ) => {
+                self.at("assignment target", |this| {
+                    this.expression(a_target, b_target)
+                });
+                self.at("assignment value", |this| this.expression(a_value, b_value));
+            }
+            (a_kind, b_kind) => self.report(describe_ast(a_kind), describe_ast(b_kind)),
+        }
+    }
+
+    fn doc(&mut self, name: &str, a: &Option<InternedStr>, b: &Option<InternedStr>) {
+        if a != b {
+            self.at(name, |this| {
+                let describe = |doc: &Option<_>| format!("{doc:?}");
+                this.report(describe(a), describe(b));
+            });
+        }
+    }
+
+    fn attribute(&mut self, a: &AstAttribute, b: &AstAttribute) {
+        self.at("attribute name", |this| this.token(&a.name, &b.name));
+        match (&a.arguments, &b.arguments) {
+            (Some(a), Some(b)) => self.list("argument", a, b, Self::expression),
+            (None, None) => {}
+            (a, b) => {
+                let describe = |arguments: &Option<Vec<AstExpression>>| {
+                    arguments
+                        .as_ref()
+                        .map_or("no arguments".into(), |arguments| {
+                            format!("{} arguments", arguments.len())
+                        })
+                };
+                self.report(describe(a), describe(b));
+            }
+        }
+    }
+
+    fn expression(&mut self, a: &AstExpression, b: &AstExpression) {
+        match (&a.kind, &b.kind) {
+            (AstExpressionKind::Name(a_name), AstExpressionKind::Name(b_name)) => {
+                if a_name != b_name {
+                    self.report(describe_expression(a), describe_expression(b));
+                }
+            }
+            (AstExpressionKind::Integer(a_value), AstExpressionKind::Integer(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_expression(a), describe_expression(b));
+                }
+            }
+            (AstExpressionKind::Float(a_value), AstExpressionKind::Float(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_expression(a), describe_expression(b));
+                }
+            }
+            (AstExpressionKind::String(a_value), AstExpressionKind::String(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_expression(a), describe_expression(b));
+                }
+            }
+            (AstExpressionKind::Char(a_value), AstExpressionKind::Char(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_expression(a), describe_expression(b));
+                }
+            }
+            (AstExpressionKind::Boolean(a_value), AstExpressionKind::Boolean(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_expression(a), describe_expression(b));
+                }
+            }
+            (AstExpressionKind::Unit, AstExpressionKind::Unit) => {}
+            (
+                AstExpressionKind::Unary {
+                    operator: a_operator,
+                    operand: a_operand,
+                },
+                AstExpressionKind::Unary {
+                    operator: b_operator,
+                    operand: b_operand,
+                },
+            ) => {
+                if a_operator != b_operator {
+                    self.at("unary operator", |this| this.report(a_operator, b_operator));
+                }
+                self.at("unary operand", |this| {
+                    this.expression(a_operand, b_operand)
+                });
+            }
+            (
+                AstExpressionKind::Binary {
+                    left: a_left,
+                    operator: a_operator,
+                    right: a_right,
+                },
+                AstExpressionKind::Binary {
+                    left: b_left,
+                    operator: b_operator,
+                    right: b_right,
+                },
+            ) => {
+                if a_operator != b_operator {
+                    self.at("binary operator", |this| {
+                        this.report(a_operator, b_operator)
+                    });
+                }
+                self.at("binary left operand", |this| {
+                    this.expression(a_left, b_left)
+                });
+                self.at("binary right operand", |this| {
+                    this.expression(a_right, b_right);
+                });
+            }
+            (
+                AstExpressionKind::StructLiteral {
+                    name: a_name,
+                    fields: a_fields,
+                    close_brace: _,
+                },
+                AstExpressionKind::StructLiteral {
+                    name: b_name,
+                    fields: b_fields,
+                    close_brace: _,
+                },
+            ) => {
+                self.at("struct name", |this| this.token(a_name, b_name));
+                self.list("field", a_fields, b_fields, |this, a, b| {
+                    this.at("field name", |this| this.token(&a.0, &b.0));
+                    this.at("field value", |this| this.expression(&a.1, &b.1));
+                });
+            }
+            (
+                AstExpressionKind::FieldAccess {
+                    operand: a_operand,
+                    name_token: a_name,
+                },
+                AstExpressionKind::FieldAccess {
+                    operand: b_operand,
+                    name_token: b_name,
+                },
+            ) => {
+                self.at("field access operand", |this| {
+                    this.expression(a_operand, b_operand)
+                });
+                self.at("field name", |this| this.token(a_name, b_name));
+            }
+            (
+                AstExpressionKind::Index {
+                    operand: a_operand,
+                    index: a_index,
+                    close_bracket: _,
+                },
+                AstExpressionKind::Index {
+                    operand: b_operand,
+                    index: b_index,
+                    close_bracket: _,
+                },
+            ) => {
+                self.at("indexed operand", |this| {
+                    this.expression(a_operand, b_operand)
+                });
+                self.at("index", |this| this.expression(a_index, b_index));
+            }
+            (
+                AstExpressionKind::Array {
+                    elements: a_elements,
+                    close_bracket: _,
+                },
+                AstExpressionKind::Array {
+                    elements: b_elements,
+                    close_bracket: _,
+                },
+            ) => self.list("element", a_elements, b_elements, Self::expression),
+            (
+                AstExpressionKind::ArrayRepeat {
+                    value: a_value,
+                    count: a_count,
+                    close_bracket: _,
+                },
+                AstExpressionKind::ArrayRepeat {
+                    value: b_value,
+                    count: b_count,
+                    close_bracket: _,
+                },
+            ) => {
+                self.at("repeated value", |this| this.expression(a_value, b_value));
+                self.at("repeat count", |this| this.expression(a_count, b_count));
+            }
+            (
+                AstExpressionKind::Range {
+                    start: a_start,
+                    end: a_end,
+                    inclusive: a_inclusive,
+                },
+                AstExpressionKind::Range {
+                    start: b_start,
+                    end: b_end,
+                    inclusive: b_inclusive,
+                },
+            ) => {
+                if a_inclusive != b_inclusive {
+                    self.at("range inclusivity", |this| {
+                        this.report(a_inclusive, b_inclusive)
+                    });
+                }
+                self.at("range start", |this| {
+                    this.optional_expression(a_start.as_deref(), b_start.as_deref());
+                });
+                self.at("range end", |this| {
+                    this.optional_expression(a_end.as_deref(), b_end.as_deref());
+                });
+            }
+            (
+                AstExpressionKind::Block {
+                    statements: a_statements,
+                    close_brace: _,
+                },
+                AstExpressionKind::Block {
+                    statements: b_statements,
+                    close_brace: _,
+                },
+            ) => self.list("statement", a_statements, b_statements, Self::ast),
+            (
+                AstExpressionKind::If {
+                    condition: a_condition,
+                    then_block: a_then_block,
+                    else_block: a_else_block,
+                },
+                AstExpressionKind::If {
+                    condition: b_condition,
+                    then_block: b_then_block,
+                    else_block: b_else_block,
+                },
+            ) => {
+                self.at("if condition", |this| {
+                    this.expression(a_condition, b_condition);
+                });
+                self.at("if then block", |this| {
+                    this.expression(a_then_block, b_then_block);
+                });
+                self.at("if else block", |this| {
+                    this.optional_expression(a_else_block.as_deref(), b_else_block.as_deref());
+                });
+            }
+            (
+                AstExpressionKind::Match {
+                    scrutinee: a_scrutinee,
+                    arms: a_arms,
+                    close_brace: _,
+                },
+                AstExpressionKind::Match {
+                    scrutinee: b_scrutinee,
+                    arms: b_arms,
+                    close_brace: _,
+                },
+            ) => {
+                self.at("match scrutinee", |this| {
+                    this.expression(a_scrutinee, b_scrutinee);
+                });
+                self.list("arm", a_arms, b_arms, |this, a, b| {
+                    this.at("arm pattern", |this| this.pattern(&a.0, &b.0));
+                    this.at("arm value", |this| this.expression(&a.1, &b.1));
+                });
+            }
+            (
+                AstExpressionKind::Call {
+                    operand: a_operand,
+                    arguments: a_arguments,
+                    commas: _,
+                    close_parenthesis: _,
+                },
+                AstExpressionKind::Call {
+                    operand: b_operand,
+                    arguments: b_arguments,
+                    commas: _,
+                    close_parenthesis: _,
+                },
+            ) => {
+                self.at("call operand", |this| this.expression(a_operand, b_operand));
+                self.list("argument", a_arguments, b_arguments, Self::expression);
+            }
+            (
+                AstExpressionKind::Closure {
+                    arguments: a_arguments,
+                    statements: a_statements,
+                    close_brace: _,
+                },
+                AstExpressionKind::Closure {
+                    arguments: b_arguments,
+                    statements: b_statements,
+                    close_brace: _,
+                },
+            ) => {
+                self.list("argument", a_arguments, b_arguments, Self::pattern);
+                self.list("statement", a_statements, b_statements, Self::ast);
+            }
+            (
+                AstExpressionKind::Paren {
+                    inner: a_inner,
+                    close_parenthesis: _,
+                },
+                AstExpressionKind::Paren {
+                    inner: b_inner,
+                    close_parenthesis: _,
+                },
+            ) => self.at("parenthesised", |this| this.expression(a_inner, b_inner)),
+            _ => self.report(describe_expression(a), describe_expression(b)),
+        }
+    }
+
+    fn pattern(&mut self, a: &AstPattern, b: &AstPattern) {
+        match (&a.kind, &b.kind) {
+            (
+                AstPatternKind::Let {
+                    name_token: a_name,
+                    typ: a_typ,
+                },
+                AstPatternKind::Let {
+                    name_token: b_name,
+                    typ: b_typ,
+                },
+            ) => {
+                self.at("pattern name", |this| this.token(a_name, b_name));
+                self.at("pattern type", |this| {
+                    this.optional_expression(a_typ.as_ref(), b_typ.as_ref());
+                });
+            }
+            (
+                AstPatternKind::Binding {
+                    name: a_name,
+                    pattern: a_pattern,
+                },
+                AstPatternKind::Binding {
+                    name: b_name,
+                    pattern: b_pattern,
+                },
+            ) => {
+                self.at("binding name", |this| this.token(a_name, b_name));
+                self.at("bound pattern", |this| this.pattern(a_pattern, b_pattern));
+            }
+            (AstPatternKind::Wildcard, AstPatternKind::Wildcard) => {}
+            (AstPatternKind::Integer(a_value), AstPatternKind::Integer(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_pattern(a), describe_pattern(b));
+                }
+            }
+            (
+                AstPatternKind::Range {
+                    start: a_start,
+                    end: a_end,
+                    inclusive: a_inclusive,
+                },
+                AstPatternKind::Range {
+                    start: b_start,
+                    end: b_end,
+                    inclusive: b_inclusive,
+                },
+            ) => {
+                if (a_start, a_end, a_inclusive) != (b_start, b_end, b_inclusive) {
+                    self.report(describe_pattern(a), describe_pattern(b));
+                }
+            }
+            (AstPatternKind::Boolean(a_value), AstPatternKind::Boolean(b_value)) => {
+                if a_value != b_value {
+                    self.report(describe_pattern(a), describe_pattern(b));
+                }
+            }
+            (AstPatternKind::Tuple(a_elements), AstPatternKind::Tuple(b_elements)) => {
+                self.list("tuple element", a_elements, b_elements, Self::pattern);
+            }
+            _ => self.report(describe_pattern(a), describe_pattern(b)),
+        }
+    }
+}
+
+fn describe_ast(kind: &AstKind) -> String {
+    match *kind {
+        AstKind::Expression(ref expression) => describe_expression(expression),
+        AstKind::Let { .. } => "Let".into(),
+        AstKind::Function { .. } => "Function".into(),
+        AstKind::ExternFunction { .. } => "ExternFunction".into(),
+        AstKind::Struct { .. } => "Struct".into(),
+        AstKind::Enum { .. } => "Enum".into(),
+        AstKind::For { .. } => "For".into(),
+        AstKind::Return { .. } => "Return".into(),
+        AstKind::Assignment { .. } => "Assignment".into(),
+    }
+}
+
+fn describe_pattern(pattern: &AstPattern) -> String {
+    match pattern.kind {
+        AstPatternKind::Let { ref name_token, .. } => format!("Let({})", name_token.kind),
+        AstPatternKind::Wildcard => "Wildcard".into(),
+        AstPatternKind::Integer(value) => format!("Integer({value})"),
+        AstPatternKind::Range {
+            start,
+            end,
+            inclusive,
+        } => format!(
+            "Range({start}{}{end})",
+            if inclusive { "..=" } else { ".." }
+        ),
+        AstPatternKind::Boolean(value) => format!("Boolean({value})"),
+        AstPatternKind::Tuple(ref elements) => format!("Tuple({})", elements.len()),
+        AstPatternKind::Binding { ref name, .. } => format!("Binding({})", name.kind),
+    }
+}
+
+fn describe_expression(expression: &AstExpression) -> String {
+    match expression.kind {
+        AstExpressionKind::Name(name) => format!("Name({name})"),
+        AstExpressionKind::Integer(value) => format!("Integer({value})"),
+        AstExpressionKind::Float(value) => format!("Float({value:?})"),
+        AstExpressionKind::String(value) => format!("String({:?})", value.to_str()),
+        AstExpressionKind::Char(value) => format!("Char({value:?})"),
+        AstExpressionKind::Boolean(value) => format!("Boolean({value})"),
+        AstExpressionKind::Unit => "Unit".into(),
+        AstExpressionKind::Unary { ref operator, .. } => format!("Unary({operator})"),
+        AstExpressionKind::Binary { ref operator, .. } => format!("Binary({operator})"),
+        AstExpressionKind::Range { inclusive, .. } => format!("Range(inclusive: {inclusive})"),
+        AstExpressionKind::Array { ref elements, .. } => format!("Array({})", elements.len()),
+        AstExpressionKind::ArrayRepeat { .. } => "ArrayRepeat".into(),
+        AstExpressionKind::Block { .. } => "Block".into(),
+        AstExpressionKind::If { .. } => "If".into(),
+        AstExpressionKind::Match { ref arms, .. } => format!("Match({})", arms.len()),
+        AstExpressionKind::Call { .. } => "Call".into(),
+        AstExpressionKind::StructLiteral { ref name, .. } => {
+            format!("StructLiteral({})", name.kind)
+        }
+        AstExpressionKind::FieldAccess { ref name_token, .. } => {
+            format!("FieldAccess({})", name_token.kind)
+        }
+        AstExpressionKind::Index { .. } => "Index".into(),
+        AstExpressionKind::Closure { .. } => "Closure".into(),
+        AstExpressionKind::Paren { .. } => "Paren".into(),
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    lexer::{LexerError, LexerErrorKind, Location},
+    parsing::{ParseError, ParseErrorKind},
+};
+use ariadne::{Color, Config, IndexType, Label, Report, ReportKind};
+use std::ops::Range;
+
+pub type Span = (&'static str, Range<usize>);
+
+impl LexerError {
+    pub fn report(&self, source: &str) -> Report<'static, Span> {
+        build_report(
+            self.location,
+            source,
+            &self.kind,
+            lexer_error_label(&self.kind),
+            None,
+        )
+    }
+}
+
+impl ParseError {
+    pub fn report(&self, source: &str) -> Report<'static, Span> {
+        let (label, help) = match self.kind {
+            ParseErrorKind::LexerError(ref kind) => (lexer_error_label(kind), None),
+            ParseErrorKind::UnexpectedToken(_) => ("this token was not expected here", None),
+            ParseErrorKind::ExpectedGlobalItem(_) => (
+                "expected an item here",
+                Some("only `fn` items, `extern fn` declarations, structs and enums, each optionally preceded by `#[attributes]`, can appear at the top level of a file"),
+            ),
+            ParseErrorKind::ExpectedExpression(_) => ("expected an expression here", None),
+            ParseErrorKind::ExpectedPattern(_) => (
+                "expected a pattern here",
+                Some("bindings are written as `let name` or `let name: type`, `_` matches anything and `(a, b)` destructures a tuple"),
+            ),
+            ParseErrorKind::ExpectedType(_) => ("expected a type here", None),
+            ParseErrorKind::InvalidAssignmentTarget => (
+                "this cannot be assigned to",
+                Some("only variables can be on the left of `=`, use `==` to compare values"),
+            ),
+            ParseErrorKind::InclusiveRangeWithoutEnd => (
+                "this `..=` has no end",
+                Some("use `..` for a range without an end"),
+            ),
+            ParseErrorKind::TooManyArguments { .. } => (
+                "this goes over the maximum list length",
+                Some("split the list up, or raise `ParserOptions::max_arguments`"),
+            ),
+            ParseErrorKind::BindingInLet => (
+                "this `@` is in a `let` statement",
+                Some("remove the `@` and the pattern after it"),
+            ),
+            ParseErrorKind::RefutablePatternInLet => (
+                "this pattern does not match every value",
+                Some("use a `match` to compare against integers and booleans"),
+            ),
+            ParseErrorKind::DuplicateFile(_) => (
+                "this file was already given",
+                Some("give each file of a module a different filepath"),
+            ),
+        };
+        build_report(self.location, source, &self.kind, label, help)
+    }
+}
+
+fn lexer_error_label(kind: &LexerErrorKind) -> &'static str {
+    match *kind {
+        LexerErrorKind::UnexpectedToken(_) => "this token was not expected here",
+        LexerErrorKind::UnexpectedChar(_) => "this character is not valid here",
+        LexerErrorKind::ReservedKeyword(_) => "this name is reserved",
+        LexerErrorKind::IntegerTooLarge => "this literal does not fit in 64 bits",
+        LexerErrorKind::IntegerTooWide { .. } => "this literal is wider than the target allows",
+        LexerErrorKind::NameTooLong { .. } => "this name is too long",
+        LexerErrorKind::DigitTooLarge { .. } => "this digit is out of range for the base",
+        LexerErrorKind::MissingDigits { .. } => "this prefix is not followed by any digits",
+        LexerErrorKind::MisplacedDigitSeparator => "this separator is not between two digits",
+        LexerErrorKind::UnterminatedString => "this string is never closed",
+        LexerErrorKind::InvalidCharLiteral => "this is not a single character",
+        LexerErrorKind::InvalidEscape(_) => "this escape sequence is not recognised",
+        LexerErrorKind::InvalidUnicodeEscape => "this unicode escape is not valid",
+        LexerErrorKind::UnterminatedBlockComment => "this comment is never closed",
+        LexerErrorKind::InconsistentLocation { .. } => "the lexer lost track of this location",
+    }
+}
+
+fn build_report(
+    location: Location,
+    source: &str,
+    message: &impl ToString,
+    label: &str,
+    help: Option<&str>,
+) -> Report<'static, Span> {
+    // locations only record where something starts, so the span covers the character there
+    let length = source[location.position..]
+        .chars()
+        .next()
+        .map_or(0, char::len_utf8);
+    let span = (
+        location.filepath.to_str(),
+        location.position..location.position + length,
+    );
+
+    let mut report = Report::build(ReportKind::Error, span.clone())
+        .with_config(Config::default().with_index_type(IndexType::Byte))
+        .with_message(message.to_string())
+        .with_label(Label::new(span).with_message(label).with_color(Color::Red));
+    if let Some(help) = help {
+        report = report.with_help(help);
+    }
+    report.finish()
+}
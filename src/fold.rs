@@ -0,0 +1,335 @@
+use crate::ast::{Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind};
+
+/// Like `Visitor`, but each node is taken by value and a possibly rewritten node is returned in its place
+pub trait Folder {
+    fn fold_ast(&mut self, ast: Ast) -> Ast {
+        fold_ast(self, ast)
+    }
+
+    fn fold_expression(&mut self, expression: AstExpression) -> AstExpression {
+        fold_expression(self, expression)
+    }
+
+    fn fold_pattern(&mut self, pattern: AstPattern) -> AstPattern {
+        fold_pattern(self, pattern)
+    }
+}
+
+/// Folds the boxed expression in place, reusing its allocation
+fn fold_boxed<F: Folder + ?Sized>(
+    folder: &mut F,
+    mut expression: Box<AstExpression>,
+) -> Box<AstExpression> {
+    *expression = folder.fold_expression(*expression);
+    expression
+}
+
+fn fold_optional<F: Folder + ?Sized>(
+    folder: &mut F,
+    expression: Option<Box<AstExpression>>,
+) -> Option<Box<AstExpression>> {
+    expression.map(|expression| fold_boxed(folder, expression))
+}
+
+/// Rebuilds `ast` from its folded children
+pub fn fold_ast<F: Folder + ?Sized>(folder: &mut F, ast: Ast) -> Ast {
+    let kind = match ast.kind {
+        AstKind::Expression(expression) => AstKind::Expression(folder.fold_expression(expression)),
+        AstKind::Let {
+            pattern,
+            equals,
+            value,
+        } => AstKind::Let {
+            pattern: folder.fold_pattern(pattern),
+            equals,
+            value: fold_boxed(folder, value),
+        },
+        AstKind::Function {
+            doc,
+            attributes,
+            name,
+            generics,
+            arguments,
+            return_type,
+            where_clause,
+            body,
+        } => AstKind::Function {
+            doc,
+            attributes,
+            name,
+            generics,
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_pattern(argument))
+                .collect(),
+            return_type: fold_optional(folder, return_type),
+            where_clause: where_clause
+                .into_iter()
+                .map(|(typ, bound)| (typ, folder.fold_expression(bound)))
+                .collect(),
+            body: folder.fold_expression(body),
+        },
+        AstKind::Return { expression } => AstKind::Return {
+            expression: folder.fold_expression(expression),
+        },
+        AstKind::ExternFunction {
+            doc,
+            attributes,
+            name,
+            arguments,
+            return_type,
+        } => AstKind::ExternFunction {
+            doc,
+            attributes,
+            name,
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_pattern(argument))
+                .collect(),
+            return_type: fold_optional(folder, return_type),
+        },
+        AstKind::Struct {
+            doc,
+            attributes,
+            name,
+            fields,
+        } => AstKind::Struct {
+            doc,
+            attributes,
+            name,
+            fields: fields
+                .into_iter()
+                .map(|(name, typ)| (name, folder.fold_expression(typ)))
+                .collect(),
+        },
+        AstKind::Enum {
+            doc,
+            attributes,
+            name,
+            variants,
+        } => AstKind::Enum {
+            doc,
+            attributes,
+            name,
+            variants: variants
+                .into_iter()
+                .map(|(name, payload)| {
+                    let payload = payload
+                        .into_iter()
+                        .map(|typ| folder.fold_expression(typ))
+                        .collect();
+                    (name, payload)
+                })
+                .collect(),
+        },
+        AstKind::For {
+            pattern,
+            iterable,
+            body,
+        } => AstKind::For {
+            pattern: folder.fold_pattern(pattern),
+            iterable: fold_boxed(folder, iterable),
+            body: fold_boxed(folder, body),
+        },
+        AstKind::Assignment {
+            target,
+            equals,
+            value,
+        } => AstKind::Assignment {
+            target: folder.fold_expression(target),
+            equals,
+            value: fold_boxed(folder, value),
+        },
+    };
+    Ast {
+        kind,
+        location: ast.location,
+    }
+}
+
+/// Rebuilds `expression` from its folded children
+pub fn fold_expression<F: Folder + ?Sized>(
+    folder: &mut F,
+    mut expression: AstExpression,
+) -> AstExpression {
+    // `AstExpression` implements `Drop`, so its kind has to be taken out instead of moved
+    let kind = match std::mem::replace(&mut expression.kind, AstExpressionKind::Unit) {
+        kind @ (AstExpressionKind::Name(_)
+        | AstExpressionKind::Integer(_)
+        | AstExpressionKind::Float(_)
+        | AstExpressionKind::String(_)
+        | AstExpressionKind::Char(_)
+        | AstExpressionKind::Boolean(_)
+        | AstExpressionKind::Unit) => kind,
+        AstExpressionKind::Unary { operator, operand } => AstExpressionKind::Unary {
+            operator,
+            operand: fold_boxed(folder, operand),
+        },
+        AstExpressionKind::Binary {
+            left,
+            operator,
+            right,
+        } => AstExpressionKind::Binary {
+            left: fold_boxed(folder, left),
+            operator,
+            right: fold_boxed(folder, right),
+        },
+        AstExpressionKind::Range {
+            start,
+            end,
+            inclusive,
+        } => AstExpressionKind::Range {
+            start: fold_optional(folder, start),
+            end: fold_optional(folder, end),
+            inclusive,
+        },
+        AstExpressionKind::Array {
+            elements,
+            close_bracket,
+        } => AstExpressionKind::Array {
+            elements: elements
+                .into_iter()
+                .map(|element| folder.fold_expression(element))
+                .collect(),
+            close_bracket,
+        },
+        AstExpressionKind::ArrayRepeat {
+            value,
+            count,
+            close_bracket,
+        } => AstExpressionKind::ArrayRepeat {
+            value: fold_boxed(folder, value),
+            count: fold_boxed(folder, count),
+            close_bracket,
+        },
+        AstExpressionKind::Block {
+            statements,
+            close_brace,
+        } => AstExpressionKind::Block {
+            statements: statements
+                .into_iter()
+                .map(|statement| folder.fold_ast(statement))
+                .collect(),
+            close_brace,
+        },
+        AstExpressionKind::If {
+            condition,
+            then_block,
+            else_block,
+        } => AstExpressionKind::If {
+            condition: fold_boxed(folder, condition),
+            then_block: fold_boxed(folder, then_block),
+            else_block: fold_optional(folder, else_block),
+        },
+        AstExpressionKind::Match {
+            scrutinee,
+            arms,
+            close_brace,
+        } => AstExpressionKind::Match {
+            scrutinee: fold_boxed(folder, scrutinee),
+            arms: arms
+                .into_iter()
+                .map(|(pattern, value)| {
+                    (folder.fold_pattern(pattern), folder.fold_expression(value))
+                })
+                .collect(),
+            close_brace,
+        },
+        AstExpressionKind::Call {
+            operand,
+            arguments,
+            commas,
+            close_parenthesis,
+        } => AstExpressionKind::Call {
+            operand: fold_boxed(folder, operand),
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_expression(argument))
+                .collect(),
+            commas,
+            close_parenthesis,
+        },
+        AstExpressionKind::StructLiteral {
+            name,
+            fields,
+            close_brace,
+        } => AstExpressionKind::StructLiteral {
+            name,
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (name, folder.fold_expression(value)))
+                .collect(),
+            close_brace,
+        },
+        AstExpressionKind::FieldAccess {
+            operand,
+            name_token,
+        } => AstExpressionKind::FieldAccess {
+            operand: fold_boxed(folder, operand),
+            name_token,
+        },
+        AstExpressionKind::Index {
+            operand,
+            index,
+            close_bracket,
+        } => AstExpressionKind::Index {
+            operand: fold_boxed(folder, operand),
+            index: fold_boxed(folder, index),
+            close_bracket,
+        },
+        AstExpressionKind::Closure {
+            arguments,
+            statements,
+            close_brace,
+        } => AstExpressionKind::Closure {
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_pattern(argument))
+                .collect(),
+            statements: statements
+                .into_iter()
+                .map(|statement| folder.fold_ast(statement))
+                .collect(),
+            close_brace,
+        },
+        AstExpressionKind::Paren {
+            inner,
+            close_parenthesis,
+        } => AstExpressionKind::Paren {
+            inner: fold_boxed(folder, inner),
+            close_parenthesis,
+        },
+    };
+    AstExpression {
+        kind,
+        location: expression.location,
+    }
+}
+
+/// Rebuilds `pattern` from its folded children
+pub fn fold_pattern<F: Folder + ?Sized>(folder: &mut F, pattern: AstPattern) -> AstPattern {
+    let kind = match pattern.kind {
+        AstPatternKind::Let { name_token, typ } => AstPatternKind::Let {
+            name_token,
+            typ: typ.map(|typ| folder.fold_expression(typ)),
+        },
+        kind @ (AstPatternKind::Wildcard
+        | AstPatternKind::Integer(_)
+        | AstPatternKind::Range { .. }
+        | AstPatternKind::Boolean(_)) => kind,
+        AstPatternKind::Tuple(elements) => AstPatternKind::Tuple(
+            elements
+                .into_iter()
+                .map(|element| folder.fold_pattern(element))
+                .collect(),
+        ),
+        AstPatternKind::Binding { name, mut pattern } => {
+            *pattern = folder.fold_pattern(*pattern);
+            AstPatternKind::Binding { name, pattern }
+        }
+    };
+    AstPattern {
+        kind,
+        location: pattern.location,
+    }
+}
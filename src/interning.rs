@@ -1,7 +1,7 @@
 use derive_more::derive::{Debug, Display};
-use lasso::{Spur, ThreadedRodeo};
+use lasso::{Capacity, Spur, ThreadedRodeo};
 use rustc_hash::FxBuildHasher;
-use std::sync::OnceLock;
+use std::{cmp::Ordering, sync::OnceLock};
 
 #[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
 #[display("{}", self.to_str())]
@@ -17,8 +17,13 @@ impl InternedStr {
         )
     }
 
+    /// The already interned `s`, without interning it if it is not
+    pub fn get(s: &str) -> Option<Self> {
+        INTERNER.get()?.get(s).map(InternedStr)
+    }
+
     pub fn to_str(self) -> &'static str {
-        // SAFETY: if this type is constructed, then `INTERNER` has already been initialized by `Self::intern`
+        // SAFETY: if this type is constructed, then `INTERNER` has already been initialized, as every constructor reads from it
         let interner = unsafe { INTERNER.get().unwrap_unchecked() };
 
         // SAFETY: if this type has been constructed, then `self.0` was retrieved from `INTERNER` so it will be resolved
@@ -26,10 +31,52 @@ impl InternedStr {
     }
 }
 
+/// Orders by the resolved strings, so unlike `Eq` a comparison is O(string length)
+impl PartialOrd for InternedStr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InternedStr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other {
+            Ordering::Equal
+        } else {
+            self.to_str().cmp(other.to_str())
+        }
+    }
+}
+
 impl From<&str> for InternedStr {
     fn from(s: &str) -> Self {
         Self::intern(s)
     }
 }
 
+/// Creates the interner with room for `capacity` strings up front, to avoid growing it while lexing large sources
+///
+/// Returns `false` without doing anything if the interner already exists, because something has been interned,
+/// so this has to be called before anything is lexed
+///
+/// There is a single interner for the whole process, so unlike a per-session interner this can only be done once
+#[must_use]
+pub fn init_with_capacity(capacity: usize) -> bool {
+    INTERNER
+        .set(ThreadedRodeo::with_capacity_and_hasher(
+            Capacity::for_strings(capacity),
+            FxBuildHasher,
+        ))
+        .is_ok()
+}
+
+/// Interns all of `strings` ahead of time, such as names that are known to be common
+///
+/// Keywords are lexed to their own `TokenKind`s and never interned, so there is no need to seed them
+pub fn seed(strings: &[&str]) {
+    for s in strings {
+        InternedStr::intern(s);
+    }
+}
+
 static INTERNER: OnceLock<ThreadedRodeo<Spur, FxBuildHasher>> = OnceLock::new();
@@ -0,0 +1,466 @@
+use crate::{
+    ast::{
+        Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind, BinaryOperator,
+        UnaryOperator,
+    },
+    interning::InternedStr,
+    lexer::{Location, TokenKind},
+};
+use derive_more::derive::Display;
+use rustc_hash::FxHashMap;
+use std::cmp::Ordering;
+use thiserror::Error;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    #[display("{_0}")]
+    Integer(i64),
+    #[display("{_0}")]
+    Bool(bool),
+    #[display("()")]
+    Unit,
+    /// An `extern fn`, called through the builtin registered under its name
+    #[display("<extern fn {_0}>")]
+    Builtin(InternedStr),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Integer(_) => "int",
+            Value::Bool(_) => "bool",
+            Value::Unit => "()",
+            Value::Builtin(_) => "fn",
+        }
+    }
+
+    /// Values can only be compared with values of the same type, anything else is a `TypeMismatch`
+    pub fn equals(&self, other: &Value) -> Result<bool, RuntimeErrorKind> {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => Ok(left == right),
+            (Value::Bool(left), Value::Bool(right)) => Ok(left == right),
+            (Value::Unit, Value::Unit) => Ok(true),
+            (Value::Builtin(left), Value::Builtin(right)) => Ok(left == right),
+            _ => Err(self.type_mismatch(other)),
+        }
+    }
+
+    /// Like `equals`, but only integers have an order, any other type is `NotOrderable`
+    pub fn compare(&self, other: &Value) -> Result<Ordering, RuntimeErrorKind> {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => Ok(left.cmp(right)),
+            _ if self.type_name() == other.type_name() => {
+                Err(RuntimeErrorKind::NotOrderable(self.type_name()))
+            }
+            _ => Err(self.type_mismatch(other)),
+        }
+    }
+
+    fn type_mismatch(&self, other: &Value) -> RuntimeErrorKind {
+        RuntimeErrorKind::TypeMismatch {
+            left: self.type_name(),
+            right: other.type_name(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RuntimeErrorKind {
+    #[error("Evaluating this expression is not supported yet")]
+    Unsupported,
+    #[error("'{0}' is not defined")]
+    UndefinedName(InternedStr),
+    #[error("Integer overflow")]
+    IntegerOverflow,
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Cannot compare a value of type '{left}' with one of type '{right}'")]
+    TypeMismatch {
+        left: &'static str,
+        right: &'static str,
+    },
+    #[error("Values of type '{0}' have no order")]
+    NotOrderable(&'static str),
+    #[error("Cannot apply '{operator}' to a value of type '{operand}'")]
+    InvalidOperand {
+        operator: UnaryOperator,
+        operand: &'static str,
+    },
+    #[error("Cannot apply '{operator}' to values of type '{left}' and '{right}'")]
+    InvalidOperands {
+        operator: BinaryOperator,
+        left: &'static str,
+        right: &'static str,
+    },
+    #[error("'{0}' is declared as an extern fn but no builtin with that name is registered")]
+    UnregisteredBuiltin(InternedStr),
+    #[error("A value of type '{0}' cannot be called")]
+    NotCallable(&'static str),
+}
+
+#[derive(Debug, Error)]
+#[error("{location}: {kind}")]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub location: Location,
+}
+
+type Scope = FxHashMap<InternedStr, Value>;
+
+/// A function implemented by the host, which `extern fn` declarations resolve to by name
+pub type Builtin = fn(&[Value]) -> Result<Value, RuntimeErrorKind>;
+
+/// The bindings visible while evaluating, lookups walk outwards from the innermost local scope to the globals
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    globals: Scope,
+    scopes: Vec<Scope>,
+    builtins: FxHashMap<InternedStr, Builtin>,
+}
+
+/// The local scopes of a caller, hidden while a function body runs
+#[derive(Debug)]
+pub struct CallerScopes(Vec<Scope>);
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines `name` in the innermost scope, or as a global if no local scope has been pushed
+    pub fn define(&mut self, name: InternedStr, value: Value) {
+        self.scopes
+            .last_mut()
+            .unwrap_or(&mut self.globals)
+            .insert(name, value);
+    }
+
+    /// Defines every name bound by `pattern` to `value`, which must match it
+    ///
+    /// There are no tuple values yet, so a tuple pattern is unsupported
+    pub fn define_pattern(
+        &mut self,
+        pattern: &AstPattern,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        match pattern.kind {
+            AstPatternKind::Let { ref name_token, .. } => {
+                let TokenKind::Name(name) = name_token.kind else {
+                    unreachable!();
+                };
+                self.define(name, value);
+            }
+            AstPatternKind::Wildcard
+            | AstPatternKind::Integer(_)
+            | AstPatternKind::Range { .. }
+            | AstPatternKind::Boolean(_) => {}
+            AstPatternKind::Tuple(_) => {
+                return Err(RuntimeError {
+                    kind: RuntimeErrorKind::Unsupported,
+                    location: pattern.location,
+                });
+            }
+            AstPatternKind::Binding {
+                ref name,
+                ref pattern,
+            } => {
+                let TokenKind::Name(name) = name.kind else {
+                    unreachable!();
+                };
+                self.define(name, value);
+                self.define_pattern(pattern, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn define_global(&mut self, name: InternedStr, value: Value) {
+        self.globals.insert(name, value);
+    }
+
+    pub fn register_builtin(&mut self, name: InternedStr, builtin: Builtin) {
+        self.builtins.insert(name, builtin);
+    }
+
+    /// Defines a global for every `extern fn` in `items`, each of which must have a registered builtin
+    pub fn define_externs(&mut self, items: &[Ast]) -> Result<(), RuntimeError> {
+        for item in items {
+            let AstKind::ExternFunction { ref name, .. } = item.kind else {
+                continue;
+            };
+            let TokenKind::Name(name_str) = name.kind else {
+                unreachable!();
+            };
+            if !self.builtins.contains_key(&name_str) {
+                return Err(RuntimeError {
+                    kind: RuntimeErrorKind::UnregisteredBuiltin(name_str),
+                    location: name.location,
+                });
+            }
+            self.define_global(name_str, Value::Builtin(name_str));
+        }
+        Ok(())
+    }
+
+    pub fn lookup(&self, name: InternedStr) -> Option<&Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name))
+            .or_else(|| self.globals.get(&name))
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    pub fn pop_scope(&mut self) {
+        let scope = self.scopes.pop();
+        debug_assert!(scope.is_some(), "the global scope cannot be popped");
+    }
+
+    /// Hides the caller's locals so a function body only sees the globals and its own scopes, as scoping is lexical
+    pub fn enter_function(&mut self) -> CallerScopes {
+        CallerScopes(std::mem::take(&mut self.scopes))
+    }
+
+    pub fn exit_function(&mut self, caller_scopes: CallerScopes) {
+        self.scopes = caller_scopes.0;
+    }
+}
+
+impl UnaryOperator {
+    /// Whether this applied to `operand` is `-9223372036854775808`, the only integer whose literal does not fit in an `i64`,
+    /// so it has to be evaluated as a whole instead of negating the literal
+    pub fn is_min_integer(&self, operand: &AstExpression) -> bool {
+        matches!(
+            (self, &operand.kind),
+            (UnaryOperator::Negate, AstExpressionKind::Integer(value)) if *value == i64::MIN.unsigned_abs()
+        )
+    }
+
+    pub fn apply(&self, operand: Value) -> Result<Value, RuntimeErrorKind> {
+        Ok(match (self, operand) {
+            (UnaryOperator::Negate, Value::Integer(value)) => Value::Integer(
+                value
+                    .checked_neg()
+                    .ok_or(RuntimeErrorKind::IntegerOverflow)?,
+            ),
+            (UnaryOperator::Not, Value::Bool(value)) => Value::Bool(!value),
+            (_, operand) => {
+                return Err(RuntimeErrorKind::InvalidOperand {
+                    operator: self.clone(),
+                    operand: operand.type_name(),
+                });
+            }
+        })
+    }
+}
+
+impl BinaryOperator {
+    /// The value of the whole expression when `left` already decides it, so the right operand is not evaluated
+    pub fn short_circuit(&self, left: Value) -> Option<Value> {
+        match (self, left) {
+            (BinaryOperator::And, Value::Bool(false)) => Some(Value::Bool(false)),
+            (BinaryOperator::Or, Value::Bool(true)) => Some(Value::Bool(true)),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, left: Value, right: Value) -> Result<Value, RuntimeErrorKind> {
+        Ok(match *self {
+            BinaryOperator::Equal => Value::Bool(left.equals(&right)?),
+            BinaryOperator::NotEqual => Value::Bool(!left.equals(&right)?),
+            BinaryOperator::Less => Value::Bool(left.compare(&right)?.is_lt()),
+            BinaryOperator::Greater => Value::Bool(left.compare(&right)?.is_gt()),
+            BinaryOperator::LessEqual => Value::Bool(left.compare(&right)?.is_le()),
+            BinaryOperator::GreaterEqual => Value::Bool(left.compare(&right)?.is_ge()),
+            // short-circuiting happens before this, in `short_circuit`, so the right operand is only evaluated when needed
+            BinaryOperator::And | BinaryOperator::Or => {
+                let (Value::Bool(left), Value::Bool(right)) = (left, right) else {
+                    return Err(RuntimeErrorKind::InvalidOperands {
+                        operator: self.clone(),
+                        left: left.type_name(),
+                        right: right.type_name(),
+                    });
+                };
+                Value::Bool(match *self {
+                    BinaryOperator::And => left && right,
+                    BinaryOperator::Or => left || right,
+                    _ => unreachable!(),
+                })
+            }
+            BinaryOperator::Add
+            | BinaryOperator::Subtract
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo => {
+                let (Value::Integer(left), Value::Integer(right)) = (left, right) else {
+                    return Err(RuntimeErrorKind::InvalidOperands {
+                        operator: self.clone(),
+                        left: left.type_name(),
+                        right: right.type_name(),
+                    });
+                };
+                Value::Integer(match *self {
+                    BinaryOperator::Add => left
+                        .checked_add(right)
+                        .ok_or(RuntimeErrorKind::IntegerOverflow)?,
+                    BinaryOperator::Subtract => left
+                        .checked_sub(right)
+                        .ok_or(RuntimeErrorKind::IntegerOverflow)?,
+                    BinaryOperator::Multiply => left
+                        .checked_mul(right)
+                        .ok_or(RuntimeErrorKind::IntegerOverflow)?,
+                    BinaryOperator::Divide => {
+                        if right == 0 {
+                            return Err(RuntimeErrorKind::DivisionByZero);
+                        }
+                        left.checked_div(right)
+                            .ok_or(RuntimeErrorKind::IntegerOverflow)?
+                    }
+                    BinaryOperator::Modulo => {
+                        if right == 0 {
+                            return Err(RuntimeErrorKind::DivisionByZero);
+                        }
+                        left.checked_rem(right)
+                            .ok_or(RuntimeErrorKind::IntegerOverflow)?
+                    }
+                    _ => unreachable!(),
+                })
+            }
+        })
+    }
+}
+
+/// Evaluates an expression that only uses literals and operators, any name is undefined
+pub fn eval_expression(expression: &AstExpression) -> Result<Value, RuntimeError> {
+    eval_expression_in(expression, &Environment::new())
+}
+
+/// Evaluates an expression that only uses literals, operators, and names defined in `environment`
+pub fn eval_expression_in(
+    expression: &AstExpression,
+    environment: &Environment,
+) -> Result<Value, RuntimeError> {
+    Ok(match expression.kind {
+        AstExpressionKind::Name(name) => *environment.lookup(name).ok_or(RuntimeError {
+            kind: RuntimeErrorKind::UndefinedName(name),
+            location: expression.location,
+        })?,
+        AstExpressionKind::Integer(value) => {
+            Value::Integer(value.try_into().map_err(|_| RuntimeError {
+                kind: RuntimeErrorKind::IntegerOverflow,
+                location: expression.location,
+            })?)
+        }
+        AstExpressionKind::Boolean(value) => Value::Bool(value),
+        AstExpressionKind::Unit => Value::Unit,
+        AstExpressionKind::Unary {
+            ref operator,
+            ref operand,
+        } if operator.is_min_integer(operand) => Value::Integer(i64::MIN),
+        AstExpressionKind::Unary {
+            ref operator,
+            ref operand,
+        } => {
+            let operand = eval_expression_in(operand, environment)?;
+            operator.apply(operand).map_err(|kind| RuntimeError {
+                kind,
+                location: expression.location,
+            })?
+        }
+        AstExpressionKind::Binary {
+            ref left,
+            ref operator,
+            ref right,
+        } => {
+            let left = eval_expression_in(left, environment)?;
+            if let Some(value) = operator.short_circuit(left) {
+                return Ok(value);
+            }
+            let right = eval_expression_in(right, environment)?;
+            operator.apply(left, right).map_err(|kind| RuntimeError {
+                kind,
+                location: expression.location,
+            })?
+        }
+        AstExpressionKind::Paren {
+            ref inner,
+            close_parenthesis: _,
+        } => eval_expression_in(inner, environment)?,
+        AstExpressionKind::Block {
+            ref statements,
+            close_brace: _,
+        } => {
+            // the block's lets are only visible inside it, so they are defined in a copy of the environment
+            let mut environment = environment.clone();
+            environment.push_scope();
+            for statement in statements {
+                match statement.kind {
+                    AstKind::Expression(ref expression) => {
+                        eval_expression_in(expression, &environment)?;
+                    }
+                    AstKind::Let {
+                        ref pattern,
+                        equals: _,
+                        ref value,
+                    } => {
+                        let value = eval_expression_in(value, &environment)?;
+                        environment.define_pattern(pattern, value)?;
+                    }
+                    _ => {
+                        return Err(RuntimeError {
+                            kind: RuntimeErrorKind::Unsupported,
+                            location: statement.location,
+                        });
+                    }
+                }
+            }
+            // a block never has the value of its last statement
+            Value::Unit
+        }
+        AstExpressionKind::Call {
+            ref operand,
+            ref arguments,
+            commas: _,
+            close_parenthesis: _,
+        } => {
+            let error = |kind| RuntimeError {
+                kind,
+                location: expression.location,
+            };
+            let callee = eval_expression_in(operand, environment)?;
+            let Value::Builtin(name) = callee else {
+                return Err(error(RuntimeErrorKind::NotCallable(callee.type_name())));
+            };
+            let arguments = arguments
+                .iter()
+                .map(|argument| eval_expression_in(argument, environment))
+                .collect::<Result<Vec<_>, _>>()?;
+            // `define` can bind a `Value::Builtin` that was never registered
+            let builtin = environment
+                .builtins
+                .get(&name)
+                .ok_or_else(|| error(RuntimeErrorKind::UnregisteredBuiltin(name)))?;
+            builtin(&arguments).map_err(error)?
+        }
+        AstExpressionKind::Float(_)
+        | AstExpressionKind::String(_)
+        | AstExpressionKind::Char(_)
+        | AstExpressionKind::Range { .. }
+        | AstExpressionKind::Array { .. }
+        | AstExpressionKind::ArrayRepeat { .. }
+        | AstExpressionKind::If { .. }
+        | AstExpressionKind::Match { .. }
+        | AstExpressionKind::StructLiteral { .. }
+        | AstExpressionKind::FieldAccess { .. }
+        | AstExpressionKind::Index { .. }
+        | AstExpressionKind::Closure { .. } => {
+            return Err(RuntimeError {
+                kind: RuntimeErrorKind::Unsupported,
+                location: expression.location,
+            });
+        }
+    })
+}
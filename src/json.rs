@@ -0,0 +1,683 @@
+use crate::{
+    analysis::{Analysis, Diagnostic},
+    ast::{
+        Ast, AstAttribute, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind,
+    },
+    interning::InternedStr,
+    lexer::{Location, Token, TokenKind},
+};
+use std::io::{Result, Write};
+
+enum Json {
+    Null,
+    Boolean(bool),
+    Integer(u64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+/// Writes the asts as a JSON array, where every node is an object with a `kind` tag and a `location`
+pub fn ast_to_json(asts: &[Ast], writer: &mut (impl Write + ?Sized), pretty: bool) -> Result<()> {
+    let json = Json::Array(asts.iter().map(ast_json).collect());
+    write_json(&json, writer, pretty.then_some(0))?;
+    if pretty {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes every diagnostic as a JSON object on its own line, errors first, so they can be read as they are written
+pub fn write_diagnostics_jsonl(
+    analysis: &Analysis,
+    writer: &mut (impl Write + ?Sized),
+) -> Result<()> {
+    let diagnostics = (analysis
+        .errors
+        .iter()
+        .map(|diagnostic| ("error", diagnostic)))
+    .chain(
+        analysis
+            .warnings
+            .iter()
+            .map(|diagnostic| ("warning", diagnostic)),
+    );
+    for (severity, diagnostic) in diagnostics {
+        write_json(&diagnostic_json(severity, diagnostic), writer, None)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn diagnostic_json(severity: &str, diagnostic: &Diagnostic) -> Json {
+    Json::Object(vec![
+        ("severity", Json::String(severity.into())),
+        ("code", Json::String(diagnostic.code.into())),
+        ("message", Json::String(diagnostic.message.clone())),
+        ("location", location_json(diagnostic.location)),
+        ("end", location_json(diagnostic.end)),
+    ])
+}
+
+fn node(kind: &str, location: Location, fields: Vec<(&'static str, Json)>) -> Json {
+    let mut object = vec![("kind", Json::String(kind.into()))];
+    object.extend(fields);
+    object.push(("location", location_json(location)));
+    Json::Object(object)
+}
+
+fn location_json(location: Location) -> Json {
+    Json::Object(vec![
+        ("filepath", Json::String(location.filepath.to_str().into())),
+        ("position", Json::Integer(location.position as _)),
+        ("line", Json::Integer(location.line.get() as _)),
+        ("column", Json::Integer(location.column.get() as _)),
+    ])
+}
+
+fn token_json(token: &Token) -> Json {
+    match token.kind {
+        TokenKind::Name(name) => Json::String(name.to_str().into()),
+        ref kind => Json::String(kind.to_string()),
+    }
+}
+
+fn ast_json(ast: &Ast) -> Json {
+    match ast.kind {
+        AstKind::Expression(ref expression) => node(
+            "Expression",
+            ast.location,
+            vec![("expression", expression_json(expression))],
+        ),
+        AstKind::Let {
+            ref pattern,
+            equals: _,
+            ref value,
+        } => node(
+            "Let",
+            ast.location,
+            vec![
+                ("pattern", pattern_json(pattern)),
+                ("value", expression_json(value)),
+            ],
+        ),
+        AstKind::Function {
+            doc,
+            ref attributes,
+            ref name,
+            ref generics,
+            ref arguments,
+            ref return_type,
+            ref where_clause,
+            ref body,
+        } => node(
+            "Function",
+            ast.location,
+            vec![
+                ("doc", doc_json(doc)),
+                (
+                    "attributes",
+                    Json::Array(attributes.iter().map(attribute_json).collect()),
+                ),
+                ("name", token_json(name)),
+                (
+                    "generics",
+                    Json::Array(generics.iter().map(token_json).collect()),
+                ),
+                (
+                    "arguments",
+                    Json::Array(arguments.iter().map(pattern_json).collect()),
+                ),
+                (
+                    "return_type",
+                    return_type.as_deref().map_or(Json::Null, expression_json),
+                ),
+                (
+                    "where_clause",
+                    Json::Array(
+                        where_clause
+                            .iter()
+                            .map(|(typ, bound)| {
+                                Json::Object(vec![
+                                    ("type", token_json(typ)),
+                                    ("bound", expression_json(bound)),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+                ("body", expression_json(body)),
+            ],
+        ),
+        AstKind::ExternFunction {
+            doc,
+            ref attributes,
+            ref name,
+            ref arguments,
+            ref return_type,
+        } => node(
+            "ExternFunction",
+            ast.location,
+            vec![
+                ("doc", doc_json(doc)),
+                (
+                    "attributes",
+                    Json::Array(attributes.iter().map(attribute_json).collect()),
+                ),
+                ("name", token_json(name)),
+                (
+                    "arguments",
+                    Json::Array(arguments.iter().map(pattern_json).collect()),
+                ),
+                (
+                    "return_type",
+                    return_type.as_deref().map_or(Json::Null, expression_json),
+                ),
+            ],
+        ),
+        AstKind::Struct {
+            doc,
+            ref attributes,
+            ref name,
+            ref fields,
+        } => node(
+            "Struct",
+            ast.location,
+            vec![
+                ("doc", doc_json(doc)),
+                (
+                    "attributes",
+                    Json::Array(attributes.iter().map(attribute_json).collect()),
+                ),
+                ("name", token_json(name)),
+                (
+                    "fields",
+                    Json::Array(
+                        fields
+                            .iter()
+                            .map(|(name, typ)| {
+                                node(
+                                    "Field",
+                                    name.location,
+                                    vec![
+                                        ("name", token_json(name)),
+                                        ("type", expression_json(typ)),
+                                    ],
+                                )
+                            })
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        AstKind::Enum {
+            doc,
+            ref attributes,
+            ref name,
+            ref variants,
+        } => node(
+            "Enum",
+            ast.location,
+            vec![
+                ("doc", doc_json(doc)),
+                (
+                    "attributes",
+                    Json::Array(attributes.iter().map(attribute_json).collect()),
+                ),
+                ("name", token_json(name)),
+                (
+                    "variants",
+                    Json::Array(
+                        variants
+                            .iter()
+                            .map(|(name, payload)| {
+                                node(
+                                    "Variant",
+                                    name.location,
+                                    vec![
+                                        ("name", token_json(name)),
+                                        (
+                                            "payload",
+                                            Json::Array(
+                                                payload.iter().map(expression_json).collect(),
+                                            ),
+                                        ),
+                                    ],
+                                )
+                            })
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        AstKind::For {
+            ref pattern,
+            ref iterable,
+            ref body,
+        } => node(
+            "For",
+            ast.location,
+            vec![
+                ("pattern", pattern_json(pattern)),
+                ("iterable", expression_json(iterable)),
+                ("body", expression_json(body)),
+            ],
+        ),
+        AstKind::Return { ref expression } => node(
+            "Return",
+            ast.location,
+            vec![("expression", expression_json(expression))],
+        ),
+        AstKind::Assignment {
+            ref target,
+            equals: _,
+            ref value,
+        } => node(
+            "Assignment",
+            ast.location,
+            vec![
+                ("target", expression_json(target)),
+                ("value", expression_json(value)),
+            ],
+        ),
+    }
+}
+
+fn doc_json(doc: Option<InternedStr>) -> Json {
+    doc.map_or(Json::Null, |doc| Json::String(doc.to_str().into()))
+}
+
+fn attribute_json(attribute: &AstAttribute) -> Json {
+    node(
+        "Attribute",
+        attribute.location,
+        vec![
+            ("name", token_json(&attribute.name)),
+            (
+                "arguments",
+                attribute
+                    .arguments
+                    .as_ref()
+                    .map_or(Json::Null, |arguments| {
+                        Json::Array(arguments.iter().map(expression_json).collect())
+                    }),
+            ),
+        ],
+    )
+}
+
+fn expression_json(expression: &AstExpression) -> Json {
+    let location = expression.location;
+    match expression.kind {
+        AstExpressionKind::Name(name) => node(
+            "Name",
+            location,
+            vec![("name", Json::String(name.to_str().into()))],
+        ),
+        AstExpressionKind::Integer(value) => {
+            node("Integer", location, vec![("value", Json::Integer(value))])
+        }
+        AstExpressionKind::Float(value) => {
+            node("Float", location, vec![("value", Json::Float(value))])
+        }
+        AstExpressionKind::String(value) => node(
+            "String",
+            location,
+            vec![("value", Json::String(value.to_str().into()))],
+        ),
+        AstExpressionKind::Char(value) => node(
+            "Char",
+            location,
+            vec![("value", Json::String(value.into()))],
+        ),
+        AstExpressionKind::Boolean(value) => {
+            node("Boolean", location, vec![("value", Json::Boolean(value))])
+        }
+        AstExpressionKind::Unit => node("Unit", location, vec![]),
+        AstExpressionKind::Unary {
+            ref operator,
+            ref operand,
+        } => node(
+            "Unary",
+            location,
+            vec![
+                ("operator", Json::String(operator.to_string())),
+                ("operand", expression_json(operand)),
+            ],
+        ),
+        AstExpressionKind::Range {
+            ref start,
+            ref end,
+            inclusive,
+        } => node(
+            "Range",
+            location,
+            vec![
+                (
+                    "start",
+                    start.as_deref().map_or(Json::Null, expression_json),
+                ),
+                ("end", end.as_deref().map_or(Json::Null, expression_json)),
+                ("inclusive", Json::Boolean(inclusive)),
+            ],
+        ),
+        AstExpressionKind::StructLiteral {
+            ref name,
+            ref fields,
+            close_brace: _,
+        } => node(
+            "StructLiteral",
+            location,
+            vec![
+                ("name", token_json(name)),
+                (
+                    "fields",
+                    Json::Array(
+                        fields
+                            .iter()
+                            .map(|(name, value)| {
+                                node(
+                                    "Field",
+                                    name.location,
+                                    vec![
+                                        ("name", token_json(name)),
+                                        ("value", expression_json(value)),
+                                    ],
+                                )
+                            })
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        AstExpressionKind::FieldAccess {
+            ref operand,
+            ref name_token,
+        } => node(
+            "FieldAccess",
+            location,
+            vec![
+                ("operand", expression_json(operand)),
+                ("name", token_json(name_token)),
+            ],
+        ),
+        AstExpressionKind::Index {
+            ref operand,
+            ref index,
+            close_bracket: _,
+        } => node(
+            "Index",
+            location,
+            vec![
+                ("operand", expression_json(operand)),
+                ("index", expression_json(index)),
+            ],
+        ),
+        AstExpressionKind::Array {
+            ref elements,
+            close_bracket: _,
+        } => node(
+            "Array",
+            location,
+            vec![(
+                "elements",
+                Json::Array(elements.iter().map(expression_json).collect()),
+            )],
+        ),
+        AstExpressionKind::ArrayRepeat {
+            ref value,
+            ref count,
+            close_bracket: _,
+        } => node(
+            "ArrayRepeat",
+            location,
+            vec![
+                ("value", expression_json(value)),
+                ("count", expression_json(count)),
+            ],
+        ),
+        AstExpressionKind::Binary {
+            ref left,
+            ref operator,
+            ref right,
+        } => node(
+            "Binary",
+            location,
+            vec![
+                ("operator", Json::String(operator.to_string())),
+                (
+                    "precedence",
+                    Json::Integer(operator.precedence().get() as _),
+                ),
+                (
+                    "associativity",
+                    Json::String(operator.associativity().to_string()),
+                ),
+                ("left", expression_json(left)),
+                ("right", expression_json(right)),
+            ],
+        ),
+        AstExpressionKind::Block {
+            ref statements,
+            close_brace: _,
+        } => node(
+            "Block",
+            location,
+            vec![(
+                "statements",
+                Json::Array(statements.iter().map(ast_json).collect()),
+            )],
+        ),
+        AstExpressionKind::If {
+            ref condition,
+            ref then_block,
+            ref else_block,
+        } => node(
+            "If",
+            location,
+            vec![
+                ("condition", expression_json(condition)),
+                ("then_block", expression_json(then_block)),
+                (
+                    "else_block",
+                    else_block.as_deref().map_or(Json::Null, expression_json),
+                ),
+            ],
+        ),
+        AstExpressionKind::Match {
+            ref scrutinee,
+            ref arms,
+            close_brace: _,
+        } => node(
+            "Match",
+            location,
+            vec![
+                ("scrutinee", expression_json(scrutinee)),
+                (
+                    "arms",
+                    Json::Array(
+                        arms.iter()
+                            .map(|(pattern, value)| {
+                                node(
+                                    "Arm",
+                                    pattern.location,
+                                    vec![
+                                        ("pattern", pattern_json(pattern)),
+                                        ("value", expression_json(value)),
+                                    ],
+                                )
+                            })
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        AstExpressionKind::Call {
+            ref operand,
+            ref arguments,
+            commas: _,
+            close_parenthesis: _,
+        } => node(
+            "Call",
+            location,
+            vec![
+                ("operand", expression_json(operand)),
+                (
+                    "arguments",
+                    Json::Array(arguments.iter().map(expression_json).collect()),
+                ),
+            ],
+        ),
+        AstExpressionKind::Closure {
+            ref arguments,
+            ref statements,
+            close_brace: _,
+        } => node(
+            "Closure",
+            location,
+            vec![
+                (
+                    "arguments",
+                    Json::Array(arguments.iter().map(pattern_json).collect()),
+                ),
+                (
+                    "statements",
+                    Json::Array(statements.iter().map(ast_json).collect()),
+                ),
+            ],
+        ),
+        AstExpressionKind::Paren {
+            ref inner,
+            close_parenthesis: _,
+        } => node("Paren", location, vec![("inner", expression_json(inner))]),
+    }
+}
+
+fn pattern_json(pattern: &AstPattern) -> Json {
+    match pattern.kind {
+        AstPatternKind::Let {
+            ref name_token,
+            ref typ,
+        } => node(
+            "Let",
+            pattern.location,
+            vec![
+                ("name", token_json(name_token)),
+                ("type", typ.as_ref().map_or(Json::Null, expression_json)),
+            ],
+        ),
+        AstPatternKind::Wildcard => node("Wildcard", pattern.location, vec![]),
+        AstPatternKind::Integer(value) => node(
+            "Integer",
+            pattern.location,
+            vec![("value", Json::Integer(value))],
+        ),
+        AstPatternKind::Range {
+            start,
+            end,
+            inclusive,
+        } => node(
+            "Range",
+            pattern.location,
+            vec![
+                ("start", Json::Integer(start)),
+                ("end", Json::Integer(end)),
+                ("inclusive", Json::Boolean(inclusive)),
+            ],
+        ),
+        AstPatternKind::Boolean(value) => node(
+            "Boolean",
+            pattern.location,
+            vec![("value", Json::Boolean(value))],
+        ),
+        AstPatternKind::Tuple(ref elements) => node(
+            "Tuple",
+            pattern.location,
+            vec![(
+                "elements",
+                Json::Array(elements.iter().map(pattern_json).collect()),
+            )],
+        ),
+        AstPatternKind::Binding {
+            ref name,
+            pattern: ref inner,
+        } => node(
+            "Binding",
+            pattern.location,
+            vec![("name", token_json(name)), ("pattern", pattern_json(inner))],
+        ),
+    }
+}
+
+fn write_json(
+    json: &Json,
+    writer: &mut (impl Write + ?Sized),
+    indent: Option<usize>,
+) -> Result<()> {
+    fn write_newline(writer: &mut (impl Write + ?Sized), indent: Option<usize>) -> Result<()> {
+        if let Some(indent) = indent {
+            writeln!(writer)?;
+            write!(writer, "{:1$}", "", indent * 2)?;
+        }
+        Ok(())
+    }
+
+    let inner_indent = indent.map(|indent| indent + 1);
+    match *json {
+        Json::Null => write!(writer, "null")?,
+        Json::Boolean(value) => write!(writer, "{value}")?,
+        Json::Integer(value) => write!(writer, "{value}")?,
+        // json has no representation for infinities or NaN
+        Json::Float(value) if !value.is_finite() => write!(writer, "null")?,
+        Json::Float(value) => write!(writer, "{value:?}")?,
+        Json::String(ref s) => write_json_string(s, writer)?,
+        Json::Array(ref elements) => {
+            write!(writer, "[")?;
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, ",")?;
+                }
+                write_newline(writer, inner_indent)?;
+                write_json(element, writer, inner_indent)?;
+            }
+            if !elements.is_empty() {
+                write_newline(writer, indent)?;
+            }
+            write!(writer, "]")?;
+        }
+        Json::Object(ref fields) => {
+            write!(writer, "{{")?;
+            for (i, (name, value)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, ",")?;
+                }
+                write_newline(writer, inner_indent)?;
+                write_json_string(name, writer)?;
+                write!(writer, "{}", if indent.is_some() { ": " } else { ":" })?;
+                write_json(value, writer, inner_indent)?;
+            }
+            if !fields.is_empty() {
+                write_newline(writer, indent)?;
+            }
+            write!(writer, "}}")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_json_string(s: &str, writer: &mut (impl Write + ?Sized)) -> Result<()> {
+    write!(writer, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            c if c.is_control() => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{c}")?,
+        }
+    }
+    write!(writer, "\"")
+}
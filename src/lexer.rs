@@ -1,6 +1,14 @@
 use crate::interning::InternedStr;
 use derive_more::derive::Display;
-use std::{iter::Peekable, num::NonZero, str::CharIndices};
+use rustc_hash::{FxBuildHasher, FxHashSet};
+use std::{
+    cell::RefCell,
+    fmt::{self, Formatter, Write},
+    mem::discriminant,
+    num::NonZero,
+    ops::Range,
+    rc::Rc,
+};
 use thiserror::Error;
 
 #[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
@@ -12,20 +20,78 @@ pub struct Location {
     pub column: NonZero<usize>,
 }
 
-#[derive(Debug, Display, Clone, PartialEq, Eq)]
+impl Location {
+    pub fn line_usize(&self) -> usize {
+        self.line.get()
+    }
+
+    pub fn column_usize(&self) -> usize {
+        self.column.get()
+    }
+
+    /// The line and column counted from 0 instead of 1
+    pub fn zero_based(&self) -> (usize, usize) {
+        (self.line.get() - 1, self.column.get() - 1)
+    }
+}
+
+/// The source with `‹›` inserted at `location`, so a location can be checked against a readable string
+///
+/// A position past the end or inside a character is moved back to the closest character boundary
+pub fn highlight(source: &str, location: Location) -> String {
+    let mut position = location.position.min(source.len());
+    while !source.is_char_boundary(position) {
+        position -= 1;
+    }
+    let (before, after) = source.split_at(position);
+    format!("{before}‹›{after}")
+}
+
+#[derive(Debug, Display, Clone, PartialEq)]
 pub enum TokenKind {
+    /// Located at the end of the source, after any trailing whitespace and newlines
     #[display("{{end of file}}")]
     EOF,
-    #[display("{_0}")]
+    #[display("{}{_0}", if is_keyword(_0.to_str()) { "r#" } else { "" })]
     Name(InternedStr),
     #[display("{_0}")]
     Integer(u64),
+    #[display("{_0:?}")]
+    Float(f64),
+    #[display("{}", StringLiteral(_0.to_str()))]
+    String(InternedStr),
+    #[display("{}", CharLiteral(*_0))]
+    Char(char),
     #[display("let")]
     Let,
     #[display("fn")]
     Fn,
+    #[display("extern")]
+    Extern,
+    #[display("struct")]
+    Struct,
+    #[display("enum")]
+    Enum,
     #[display("return")]
     Return,
+    #[display("where")]
+    Where,
+    #[display("if")]
+    If,
+    #[display("else")]
+    Else,
+    #[display("match")]
+    Match,
+    #[display("for")]
+    For,
+    #[display("in")]
+    In,
+    #[display("true")]
+    True,
+    #[display("false")]
+    False,
+    #[display("_")]
+    Underscore,
     #[display("(")]
     OpenParenthesis,
     #[display(")")]
@@ -34,6 +100,10 @@ pub enum TokenKind {
     OpenBrace,
     #[display("}}")]
     CloseBrace,
+    #[display("[")]
+    OpenBracket,
+    #[display("]")]
+    CloseBracket,
     #[display(",")]
     Comma,
     #[display(":")]
@@ -42,6 +112,24 @@ pub enum TokenKind {
     Semicolon,
     #[display("=")]
     Equals,
+    #[display("==")]
+    EqualsEquals,
+    #[display("!=")]
+    BangEquals,
+    #[display("<")]
+    Less,
+    #[display(">")]
+    Greater,
+    #[display("<=")]
+    LessEquals,
+    #[display(">=")]
+    GreaterEquals,
+    #[display("!")]
+    Bang,
+    #[display("&&")]
+    AmpersandAmpersand,
+    #[display("||")]
+    PipePipe,
     #[display("+")]
     Plus,
     #[display("-")]
@@ -50,11 +138,126 @@ pub enum TokenKind {
     Asterisk,
     #[display("/")]
     Slash,
+    #[display("%")]
+    Percent,
     #[display("->")]
     RightArrow,
+    #[display("=>")]
+    FatArrow,
+    #[display(".")]
+    Dot,
+    #[display("..")]
+    DotDot,
+    #[display("..=")]
+    DotDotEquals,
+    #[display("#")]
+    Hash,
+    #[display("@")]
+    At,
+    /// Only produced when `LexerOptions::emit_comments` is enabled
+    #[display("{{comment}}")]
+    Comment,
+    /// A `//!` comment documenting the file it is in, which is never trivia
+    #[display("//!{_0}")]
+    InnerDocComment(InternedStr),
+    /// A `/** ... */` comment documenting the next item, with the `*` margins already removed, which is never trivia
+    /// but is skipped by `Lexer::next_token`, as only `Lexer::next_doc_comment` reads it before an item
+    #[display("/** {_0} */")]
+    DocComment(InternedStr),
+}
+
+/// Displays a string as a literal that lexes back to the same string, with quotes and escapes
+pub struct StringLiteral<'a>(pub &'a str);
+
+impl fmt::Display for StringLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            write_escaped(f, c, '"')?;
+        }
+        f.write_char('"')
+    }
+}
+
+/// Displays a char as a literal that lexes back to the same char, with quotes and escapes
+pub struct CharLiteral(pub char);
+
+impl fmt::Display for CharLiteral {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_char('\'')?;
+        write_escaped(f, self.0, '\'')?;
+        f.write_char('\'')
+    }
+}
+
+fn write_escaped(f: &mut Formatter<'_>, c: char, quote: char) -> fmt::Result {
+    match c {
+        '\\' => f.write_str("\\\\"),
+        '\n' => f.write_str("\\n"),
+        '\t' => f.write_str("\\t"),
+        '\r' => f.write_str("\\r"),
+        '\0' => f.write_str("\\0"),
+        c if c == quote => write!(f, "\\{c}"),
+        c if c.is_control() => write!(f, "\\u{{{:X}}}", c as u32),
+        c => f.write_char(c),
+    }
+}
+
+/// Removes the leading whitespace and `*` margin from each line, along with blank lines at the start and end
+fn clean_block_doc(contents: &str) -> String {
+    let lines = contents
+        .lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .position(|line| !line.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(start, |end| end + 1);
+    lines[start..end].join("\n")
+}
+
+fn keyword(name: &str) -> Option<TokenKind> {
+    Some(match name {
+        "let" => TokenKind::Let,
+        "fn" => TokenKind::Fn,
+        "extern" => TokenKind::Extern,
+        "struct" => TokenKind::Struct,
+        "enum" => TokenKind::Enum,
+        "return" => TokenKind::Return,
+        "where" => TokenKind::Where,
+        "if" => TokenKind::If,
+        "else" => TokenKind::Else,
+        "match" => TokenKind::Match,
+        "for" => TokenKind::For,
+        "in" => TokenKind::In,
+        "true" => TokenKind::True,
+        "false" => TokenKind::False,
+        "_" => TokenKind::Underscore,
+        _ => return None,
+    })
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Whether `name` has to be written as a raw name like `r#fn`, as it would otherwise lex as a keyword
+pub fn is_keyword(name: &str) -> bool {
+    keyword(name).is_some()
+}
+
+impl TokenKind {
+    /// Whether the parser should skip this token
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenKind::Comment)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub location: Location,
@@ -62,12 +265,130 @@ pub struct Token {
 
 #[derive(Debug, Error)]
 pub enum LexerErrorKind {
+    #[error("Unexpected token '{0}'")]
+    UnexpectedToken(TokenKind),
     #[error("Unexpected character '{0}'")]
     UnexpectedChar(char),
+    #[error("'{0}' is reserved as a keyword")]
+    ReservedKeyword(InternedStr),
     #[error("Integer literal is too large")]
     IntegerTooLarge,
+    #[error("Integer literal does not fit in {bits} bits")]
+    IntegerTooWide { bits: u32 },
+    #[error("Name is longer than {max} characters")]
+    NameTooLong { max: usize },
     #[error("Digit of base {base} integer is too large")]
     DigitTooLarge { base: u8 },
+    #[error("Base {base} integer literal has no digits after its prefix")]
+    MissingDigits { base: u8 },
+    #[error("String literal is never closed with '\"'")]
+    UnterminatedString,
+    #[error("Character literal must contain exactly one character")]
+    InvalidCharLiteral,
+    #[error("Unknown escape sequence '\\{0}'")]
+    InvalidEscape(char),
+    #[error("Unicode escapes must be written as '\\u{{...}}' with at most 6 hex digits of a valid code point")]
+    InvalidUnicodeEscape,
+    #[error("Block comment is never closed with '*/'")]
+    UnterminatedBlockComment,
+    /// Reported at the offending `_`, a leading `_` like `_1` never starts a number, it is lexed as a name
+    #[error("Digit separators must be placed between two digits")]
+    MisplacedDigitSeparator,
+    #[error(
+        "Internal error: tracked location is inconsistent with the source, expected {expected}"
+    )]
+    InconsistentLocation { expected: Location },
+}
+
+impl LexerErrorKind {
+    /// The name of the variant, which stays the same when the message is reworded
+    pub fn code(&self) -> &'static str {
+        match *self {
+            LexerErrorKind::UnexpectedToken(_) => "UnexpectedToken",
+            LexerErrorKind::UnexpectedChar(_) => "UnexpectedChar",
+            LexerErrorKind::ReservedKeyword(_) => "ReservedKeyword",
+            LexerErrorKind::IntegerTooLarge => "IntegerTooLarge",
+            LexerErrorKind::IntegerTooWide { .. } => "IntegerTooWide",
+            LexerErrorKind::NameTooLong { .. } => "NameTooLong",
+            LexerErrorKind::DigitTooLarge { .. } => "DigitTooLarge",
+            LexerErrorKind::MissingDigits { .. } => "MissingDigits",
+            LexerErrorKind::UnterminatedString => "UnterminatedString",
+            LexerErrorKind::InvalidCharLiteral => "InvalidCharLiteral",
+            LexerErrorKind::InvalidEscape(_) => "InvalidEscape",
+            LexerErrorKind::InvalidUnicodeEscape => "InvalidUnicodeEscape",
+            LexerErrorKind::UnterminatedBlockComment => "UnterminatedBlockComment",
+            LexerErrorKind::MisplacedDigitSeparator => "MisplacedDigitSeparator",
+            LexerErrorKind::InconsistentLocation { .. } => "InconsistentLocation",
+        }
+    }
+
+    /// A longer description of the error and how it is usually fixed
+    pub fn explanation(&self) -> &'static str {
+        match *self {
+            LexerErrorKind::UnexpectedToken(_) => {
+                "A different token was required at this point. \
+                 Check for a missing or misplaced punctuation character just before it."
+            }
+            LexerErrorKind::UnexpectedChar(_) => {
+                "This character cannot start any token. \
+                 Names use ASCII letters, digits and underscores, \
+                 and a character that looks like a space may be a pasted non-breaking space."
+            }
+            LexerErrorKind::ReservedKeyword(_) => {
+                "This name is reserved for a future keyword and cannot be used as an identifier. \
+                 Rename it, or write it as a raw name like `r#name`."
+            }
+            LexerErrorKind::IntegerTooLarge => {
+                "Integer literals must fit in an unsigned 64 bit integer, \
+                 the largest being 18446744073709551615 or `0xFFFF_FFFF_FFFF_FFFF`."
+            }
+            LexerErrorKind::IntegerTooWide { .. } => {
+                "The lexer was configured with a narrower maximum integer width than this literal needs. \
+                 Use a smaller value, or raise `LexerOptions::max_int_bits`."
+            }
+            LexerErrorKind::NameTooLong { .. } => {
+                "The lexer was configured with a maximum name length that this name exceeds. \
+                 Use a shorter name, or raise `LexerOptions::max_name_length`."
+            }
+            LexerErrorKind::DigitTooLarge { .. } => {
+                "Every digit must be smaller than the literal's base, \
+                 which is set by a prefix like `0x`, `0o` or `0b` and is 10 otherwise. \
+                 A letter directly after a number is also read as a digit, so separate it with a space."
+            }
+            LexerErrorKind::MissingDigits { .. } => {
+                "A base prefix like `0x`, `0o`, `0b` or `0d` has to be followed by at least one digit, \
+                 so write `0x0` rather than `0x` for zero."
+            }
+            LexerErrorKind::MisplacedDigitSeparator => {
+                "An `_` in a number must sit between two digits, as in `1_000`. \
+                 Remove separators at the start or end of the digits, or directly after a base prefix."
+            }
+            LexerErrorKind::UnterminatedString => {
+                "A string literal has to be closed with a `\"` on the same line it starts on, \
+                 unless the line ends with a `\\` to continue the string on the next line."
+            }
+            LexerErrorKind::InvalidCharLiteral => {
+                "A character literal like `'a'` holds exactly one character or escape sequence. \
+                 Use a string literal like `\"ab\"` for any other number of characters."
+            }
+            LexerErrorKind::InvalidEscape(_) => {
+                "Only `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\'` and `\\u{...}` escapes are allowed in literals. \
+                 A literal backslash has to be written as `\\\\`."
+            }
+            LexerErrorKind::InvalidUnicodeEscape => {
+                "A unicode escape is written as `\\u{1F600}`, with between 1 and 6 hex digits in braces \
+                 that name a unicode scalar value, so surrogates and values above `10FFFF` are not allowed."
+            }
+            LexerErrorKind::UnterminatedBlockComment => {
+                "A `/*` comment continues until the next `*/`, which was never found. \
+                 Close the comment, or use `//` for a comment that ends with the line."
+            }
+            LexerErrorKind::InconsistentLocation { .. } => {
+                "The lexer's tracked location disagreed with the source while validating locations. \
+                 This is a bug in the lexer rather than in the program being lexed."
+            }
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -77,24 +398,164 @@ pub struct LexerError {
     pub location: Location,
 }
 
+/// Lexes the whole source, including doc comments, the last token is always `EOF`
+pub fn tokenize(filepath: InternedStr, source: &str) -> Result<Vec<Token>, LexerError> {
+    let lexer = &mut Lexer::new(filepath, source);
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next_significant_token()?;
+        let is_eof = matches!(token.kind, TokenKind::EOF);
+        tokens.push(token);
+        if is_eof {
+            break Ok(tokens);
+        }
+    }
+}
+
+/// What a column in a `Location` counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnUnit {
+    Chars,
+    Utf8Bytes,
+    Utf16CodeUnits,
+}
+
+#[derive(Debug, Clone)]
+pub struct LexerOptions {
+    /// Names that cannot be used as identifiers because they are reserved as future keywords, defaults to none
+    pub reserved: FxHashSet<InternedStr>,
+    /// The widest integer literal accepted in bits, defaults to 64
+    pub max_int_bits: u32,
+    /// The longest name accepted in characters, defaults to no limit
+    pub max_name_length: Option<usize>,
+    /// A tab moves the column to the next multiple of this, defaults to 1 so a tab is a single column
+    pub tab_width: NonZero<usize>,
+    /// Defaults to `ColumnUnit::Chars`
+    pub column_unit: ColumnUnit,
+    /// Whether comments are produced as `TokenKind::Comment` trivia from `next_raw_token`, defaults to `false`
+    pub emit_comments: bool,
+    /// Whether only ASCII whitespace is skipped, so other spaces like a pasted NBSP are an `UnexpectedChar`,
+    /// defaults to `false`
+    pub ascii_whitespace_only: bool,
+    /// Whether every token's location is recomputed from the source and compared against the tracked one,
+    /// reporting `InconsistentLocation` on a mismatch, defaults to `false`
+    pub validate_locations: bool,
+}
+
+static DEFAULT_LEXER_OPTIONS: LexerOptions = LexerOptions {
+    reserved: FxHashSet::with_hasher(FxBuildHasher),
+    max_int_bits: u64::BITS,
+    max_name_length: None,
+    tab_width: NonZero::<usize>::MIN,
+    column_unit: ColumnUnit::Chars,
+    emit_comments: false,
+    ascii_whitespace_only: false,
+    validate_locations: false,
+};
+
+impl Default for LexerOptions {
+    fn default() -> Self {
+        DEFAULT_LEXER_OPTIONS.clone()
+    }
+}
+
+impl LexerOptions {
+    fn advance_line_and_column(&self, location: &mut Location, c: char) {
+        if c == '\n' {
+            location.line = location.line.saturating_add(1);
+            location.column = NonZero::<usize>::MIN;
+        } else if c == '\t' {
+            let tab_width = self.tab_width.get();
+            let column = (location.column.get() - 1) / tab_width * tab_width + tab_width;
+            location.column = NonZero::<usize>::MIN.saturating_add(column);
+        } else {
+            location.column = location.column.saturating_add(match self.column_unit {
+                ColumnUnit::Chars => 1,
+                ColumnUnit::Utf8Bytes => c.len_utf8(),
+                ColumnUnit::Utf16CodeUnits => c.len_utf16(),
+            });
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Lexer<'source> {
+    options: &'source LexerOptions,
     location: Location,
     source: &'source str,
-    chars: Peekable<CharIndices<'source>>,
+    validated_location: Location,
+    /// Shared with the clones made to look ahead, so a token that was only peeked at is recorded as well
+    recorded: Option<Rc<RefCell<Vec<Token>>>>,
 }
 
 impl<'source> Lexer<'source> {
     pub fn new(filepath: InternedStr, source: &'source str) -> Self {
+        Self::new_with_options(filepath, source, &DEFAULT_LEXER_OPTIONS)
+    }
+
+    pub fn new_with_options(
+        filepath: InternedStr,
+        source: &'source str,
+        options: &'source LexerOptions,
+    ) -> Self {
+        let location = Location {
+            filepath,
+            position: 0,
+            line: NonZero::<usize>::MIN,
+            column: NonZero::<usize>::MIN,
+        };
+        Self::new_at(source, location, options)
+    }
+
+    /// A lexer that starts at `location` instead of the start of the source,
+    /// which must be the location of a character boundary in `source`
+    pub fn new_at(
+        source: &'source str,
+        location: Location,
+        options: &'source LexerOptions,
+    ) -> Self {
+        debug_assert!(source.is_char_boundary(location.position));
         Self {
-            location: Location {
-                filepath,
-                position: 0,
-                line: NonZero::<usize>::MIN,
-                column: NonZero::<usize>::MIN,
-            },
+            options,
+            location,
             source,
-            chars: source.char_indices().peekable(),
+            validated_location: location,
+            recorded: None,
+        }
+    }
+
+    /// Lexes `source` again after a single edit at the byte offset `offset`, reusing the tokens of `previous`,
+    /// the significant tokens of the source before the edit lexed with the same `options`, up to a point the edit
+    /// cannot have affected
+    ///
+    /// Lexing restarts at the token before the one containing the edit, as a token can depend on the character
+    /// after it, and continues to `EOF`, so with the default options the result is the same as `tokenize` on the
+    /// new source. An empty `previous` has nothing to reuse, so all of `source` is lexed
+    pub fn relex_from(
+        filepath: InternedStr,
+        previous: &[Token],
+        source: &str,
+        offset: usize,
+        options: &LexerOptions,
+    ) -> Result<Vec<Token>, LexerError> {
+        let restart = previous
+            .partition_point(|token| token.location.position < offset)
+            .saturating_sub(2);
+
+        // the first token can move when the edit is before it, so that restarts from the start of the source
+        let mut lexer = if restart == 0 {
+            Lexer::new_with_options(filepath, source, options)
+        } else {
+            Lexer::new_at(source, previous[restart].location, options)
+        };
+        let mut tokens = previous[..restart].to_vec();
+        loop {
+            let token = lexer.next_significant_token()?;
+            let is_eof = matches!(token.kind, TokenKind::EOF);
+            tokens.push(token);
+            if is_eof {
+                break Ok(tokens);
+            }
         }
     }
 
@@ -107,29 +568,230 @@ impl<'source> Lexer<'source> {
     }
 
     pub fn next_char(&mut self) -> Option<char> {
-        let (pos, c) = self.chars.next()?;
-        debug_assert_eq!(pos, self.location.position);
+        let c = self.peek_char()?;
+        self.location.position += c.len_utf8();
 
-        self.location.position = self
-            .chars
-            .peek()
-            .map(|&(pos, _)| pos)
-            .unwrap_or(self.source.len());
+        self.options.advance_line_and_column(&mut self.location, c);
 
-        self.location.column = self.location.column.saturating_add(1);
-        if c == '\n' {
-            self.location.line = self.location.line.saturating_add(1);
-            self.location.column = NonZero::<usize>::MIN;
+        Some(c)
+    }
+
+    /// Consumes the next token if it is the same kind as `kind`, ignoring any payload like a name's value
+    pub fn eat(&mut self, kind: &TokenKind) -> Result<Option<Token>, LexerError> {
+        let mut lexer = self.clone();
+        let token = lexer.next_token()?;
+        Ok(if discriminant(&token.kind) == discriminant(kind) {
+            *self = lexer;
+            Some(token)
+        } else {
+            None
+        })
+    }
+
+    /// Consumes the next token, which must be the same kind as `kind`, ignoring any payload like a name's value
+    pub fn expect(&mut self, kind: &TokenKind) -> Result<Token, LexerError> {
+        let token = self.next_token()?;
+        if discriminant(&token.kind) == discriminant(kind) {
+            Ok(token)
+        } else {
+            Err(LexerError {
+                kind: LexerErrorKind::UnexpectedToken(token.kind),
+                location: token.location,
+            })
         }
+    }
 
-        Some(c)
+    /// Every token including trivia, up to and including `EOF`, stopping after the first error
+    pub fn tokens(self) -> Tokens<'source> {
+        Tokens {
+            lexer: self,
+            finished: false,
+        }
+    }
+
+    /// Like `tokens` but without trivia
+    pub fn significant_tokens(self) -> impl Iterator<Item = Result<Token, LexerError>> + 'source {
+        self.tokens()
+            .filter(|token| token.as_ref().map_or(true, |token| !token.kind.is_trivia()))
+    }
+
+    /// The byte range of every token before `EOF`, including trivia, for highlighting the source
+    ///
+    /// The ranges are in order and never overlap, anything between two of them is whitespace,
+    /// or a comment when `LexerOptions::emit_comments` is disabled
+    pub fn token_ranges(
+        mut self,
+    ) -> impl Iterator<Item = Result<(TokenKind, Range<usize>), LexerError>> + 'source {
+        let mut finished = false;
+        std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+            match self.next_raw_token() {
+                Ok(Token {
+                    kind: TokenKind::EOF,
+                    ..
+                }) => {
+                    finished = true;
+                    None
+                }
+                Ok(token) => Some(Ok((
+                    token.kind,
+                    token.location.position..self.location.position,
+                ))),
+                Err(error) => {
+                    finished = true;
+                    Some(Err(error))
+                }
+            }
+        })
     }
 
     pub fn peek_token(&self) -> Result<Token, LexerError> {
         self.clone().next_token()
     }
 
+    /// The next token that is not trivia or a doc comment
+    ///
+    /// Doc comments are only read with `next_doc_comment` before an item, anywhere else they are ordinary comments
     pub fn next_token(&mut self) -> Result<Token, LexerError> {
+        loop {
+            let token = self.next_significant_token()?;
+            if !matches!(token.kind, TokenKind::DocComment(_)) {
+                break Ok(token);
+            }
+        }
+    }
+
+    /// The next token that is not trivia, including doc comments
+    pub fn next_significant_token(&mut self) -> Result<Token, LexerError> {
+        loop {
+            let token = self.next_raw_token()?;
+            if !token.kind.is_trivia() {
+                if let Some(recorded) = &self.recorded {
+                    // a clone that looked ahead may have recorded this token or ones after it already
+                    let mut recorded = recorded.borrow_mut();
+                    let start = recorded.partition_point(|previous| {
+                        previous.location.position < token.location.position
+                    });
+                    recorded.truncate(start);
+                    recorded.push(token.clone());
+                }
+                break Ok(token);
+            }
+        }
+    }
+
+    /// Starts recording every significant token this lexer and its clones lex, which `recorded_tokens` returns
+    pub fn record_tokens(&mut self) {
+        self.recorded = Some(Rc::default());
+    }
+
+    /// The significant tokens lexed since `record_tokens`, in source order and each only once
+    pub fn recorded_tokens(&self) -> Vec<Token> {
+        self.recorded
+            .as_ref()
+            .map_or_else(Vec::new, |recorded| recorded.borrow().clone())
+    }
+
+    /// Consumes the next token if it is a doc comment, returning its text
+    pub fn next_doc_comment(&mut self) -> Result<Option<InternedStr>, LexerError> {
+        let mut lexer = self.clone();
+        Ok(match lexer.next_significant_token()?.kind {
+            TokenKind::DocComment(doc) => {
+                *self = lexer;
+                Some(doc)
+            }
+            _ => None,
+        })
+    }
+
+    /// The next token, including trivia
+    pub fn next_raw_token(&mut self) -> Result<Token, LexerError> {
+        let token = self.lex_token()?;
+        if self.options.validate_locations {
+            self.validate_location(token.location)?;
+            self.validate_location(self.location)?;
+        }
+        Ok(token)
+    }
+
+    fn validate_location(&mut self, location: Location) -> Result<(), LexerError> {
+        let mut expected = self.validated_location;
+        if location.position < expected.position || !self.source.is_char_boundary(location.position)
+        {
+            return Err(LexerError {
+                kind: LexerErrorKind::InconsistentLocation { expected },
+                location,
+            });
+        }
+
+        for c in self.source[expected.position..location.position].chars() {
+            self.options.advance_line_and_column(&mut expected, c);
+        }
+        expected.position = location.position;
+
+        if expected != location {
+            return Err(LexerError {
+                kind: LexerErrorKind::InconsistentLocation { expected },
+                location,
+            });
+        }
+        self.validated_location = expected;
+        Ok(())
+    }
+
+    /// Lexes the rest of an escape sequence after the `\\`, `unterminated` is returned when the line or file ends
+    fn lex_escape(
+        &mut self,
+        unterminated: impl FnOnce() -> LexerError,
+    ) -> Result<char, LexerError> {
+        let escape_location = self.location;
+        Ok(match self.next_char() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('u') => self.lex_unicode_escape(escape_location)?,
+            Some('\n') | None => return Err(unterminated()),
+            Some(c) => {
+                return Err(LexerError {
+                    kind: LexerErrorKind::InvalidEscape(c),
+                    location: escape_location,
+                });
+            }
+        })
+    }
+
+    /// Lexes the `{1F600}` part of a `\u{1F600}` escape
+    fn lex_unicode_escape(&mut self, escape_location: Location) -> Result<char, LexerError> {
+        let error = || LexerError {
+            kind: LexerErrorKind::InvalidUnicodeEscape,
+            location: escape_location,
+        };
+        if self.next_char() != Some('{') {
+            return Err(error());
+        }
+        let mut code_point = 0u32;
+        let mut digits = 0;
+        while let Some(digit) = self.peek_char().and_then(|c| c.to_digit(16)) {
+            self.next_char();
+            digits += 1;
+            if digits > 6 {
+                return Err(error());
+            }
+            code_point = code_point * 16 + digit;
+        }
+        if digits == 0 || self.next_char() != Some('}') {
+            return Err(error());
+        }
+        char::from_u32(code_point).ok_or_else(error)
+    }
+
+    fn lex_token(&mut self) -> Result<Token, LexerError> {
         loop {
             let start_location = self.location;
             break Ok(Token {
@@ -141,10 +803,67 @@ impl<'source> Lexer<'source> {
                     Some(')') => TokenKind::CloseParenthesis,
                     Some('{') => TokenKind::OpenBrace,
                     Some('}') => TokenKind::CloseBrace,
+                    Some('[') => TokenKind::OpenBracket,
+                    Some(']') => TokenKind::CloseBracket,
                     Some(',') => TokenKind::Comma,
                     Some(':') => TokenKind::Colon,
                     Some(';') => TokenKind::Semicolon,
-                    Some('=') => TokenKind::Equals,
+                    Some('=') => match self.peek_char() {
+                        Some('=') => {
+                            self.next_char();
+                            TokenKind::EqualsEquals
+                        }
+                        Some('>') => {
+                            self.next_char();
+                            TokenKind::FatArrow
+                        }
+                        _ => TokenKind::Equals,
+                    },
+                    Some('!') => {
+                        if let Some('=') = self.peek_char() {
+                            self.next_char();
+                            TokenKind::BangEquals
+                        } else {
+                            TokenKind::Bang
+                        }
+                    }
+                    Some('&') if self.peek_char() == Some('&') => {
+                        self.next_char();
+                        TokenKind::AmpersandAmpersand
+                    }
+                    Some('|') if self.peek_char() == Some('|') => {
+                        self.next_char();
+                        TokenKind::PipePipe
+                    }
+                    Some('<') => {
+                        if let Some('=') = self.peek_char() {
+                            self.next_char();
+                            TokenKind::LessEquals
+                        } else {
+                            TokenKind::Less
+                        }
+                    }
+                    Some('>') => {
+                        if let Some('=') = self.peek_char() {
+                            self.next_char();
+                            TokenKind::GreaterEquals
+                        } else {
+                            TokenKind::Greater
+                        }
+                    }
+                    // a `.` directly after digits is part of a float, which the number lexing already handled
+                    Some('.') => match self.peek_char() {
+                        Some('.') => {
+                            self.next_char();
+                            if let Some('=') = self.peek_char() {
+                                self.next_char();
+                                TokenKind::DotDotEquals
+                            } else {
+                                TokenKind::DotDot
+                            }
+                        }
+                        _ => TokenKind::Dot,
+                    },
                     Some('+') => TokenKind::Plus,
                     Some('-') => {
                         if let Some('>') = self.peek_char() {
@@ -155,71 +874,296 @@ impl<'source> Lexer<'source> {
                         }
                     }
                     Some('*') => TokenKind::Asterisk,
-                    Some('/') => TokenKind::Slash,
+                    Some('/') => match self.peek_char() {
+                        Some('/') => {
+                            while self.peek_char().is_some_and(|c| c != '\n') {
+                                self.next_char();
+                            }
+                            let text =
+                                &self.source[start_location.position..self.location.position];
+                            if let Some(doc) = text.strip_prefix("//!") {
+                                TokenKind::InnerDocComment(InternedStr::intern(doc))
+                            } else {
+                                if !self.options.emit_comments {
+                                    continue;
+                                }
+                                TokenKind::Comment
+                            }
+                        }
+                        Some('*') => {
+                            self.next_char();
+                            // block comments nest, so the comment only ends once every `/*` is closed
+                            let mut depth = 1usize;
+                            while depth > 0 {
+                                match self.next_char() {
+                                    Some('*') if self.peek_char() == Some('/') => {
+                                        self.next_char();
+                                        depth -= 1;
+                                    }
+                                    Some('/') if self.peek_char() == Some('*') => {
+                                        self.next_char();
+                                        depth += 1;
+                                    }
+                                    Some(_) => {}
+                                    None => {
+                                        return Err(LexerError {
+                                            kind: LexerErrorKind::UnterminatedBlockComment,
+                                            location: start_location,
+                                        });
+                                    }
+                                }
+                            }
+                            let text =
+                                &self.source[start_location.position..self.location.position];
+                            // `/**/` and `/*** ... */` are ordinary comments, like in rust
+                            if text.starts_with("/**")
+                                && !text.starts_with("/***")
+                                && text != "/**/"
+                            {
+                                TokenKind::DocComment(InternedStr::intern(&clean_block_doc(
+                                    &text["/**".len()..text.len() - "*/".len()],
+                                )))
+                            } else {
+                                if !self.options.emit_comments {
+                                    continue;
+                                }
+                                TokenKind::Comment
+                            }
+                        }
+                        _ => TokenKind::Slash,
+                    },
+                    Some('%') => TokenKind::Percent,
+                    Some('#') => TokenKind::Hash,
+                    Some('@') => TokenKind::At,
+
+                    Some('"') => {
+                        let unterminated = || LexerError {
+                            kind: LexerErrorKind::UnterminatedString,
+                            location: start_location,
+                        };
+                        let mut value = String::new();
+                        loop {
+                            match self.next_char() {
+                                Some('"') => break,
+                                Some('\n') | None => return Err(unterminated()),
+                                // A `\` at the end of a line skips the newline and the indentation after it
+                                Some('\\') if self.peek_char() == Some('\n') => {
+                                    self.next_char();
+                                    while let Some(' ' | '\t') = self.peek_char() {
+                                        self.next_char();
+                                    }
+                                }
+                                Some('\\') => value.push(self.lex_escape(unterminated)?),
+                                Some(c) => value.push(c),
+                            }
+                        }
+                        TokenKind::String(InternedStr::intern(&value))
+                    }
+
+                    Some('\'') => {
+                        let invalid = || LexerError {
+                            kind: LexerErrorKind::InvalidCharLiteral,
+                            location: start_location,
+                        };
+                        let value = match self.next_char() {
+                            Some('\\') => self.lex_escape(invalid)?,
+                            Some('\'' | '\n') | None => return Err(invalid()),
+                            Some(c) => c,
+                        };
+                        if self.next_char() != Some('\'') {
+                            return Err(invalid());
+                        }
+                        TokenKind::Char(value)
+                    }
 
                     Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                        // `r#name` is a raw name, which is never treated as a keyword or reserved
+                        let raw = c == 'r' && {
+                            let mut rest = self.source[self.location.position..].chars();
+                            rest.next() == Some('#')
+                                && rest
+                                    .next()
+                                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                        };
+                        let name_start = if raw {
+                            self.next_char();
+                            self.location.position
+                        } else {
+                            start_location.position
+                        };
+
                         while self
                             .peek_char()
-                            .map_or(false, |c| c.is_ascii_alphanumeric() || c == '_')
+                            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
                         {
                             self.next_char();
                         }
 
-                        match &self.source[start_location.position..self.location.position] {
-                            "let" => TokenKind::Let,
-                            "fn" => TokenKind::Fn,
-                            "return" => TokenKind::Return,
-                            name => TokenKind::Name(name.into()),
+                        let name = &self.source[name_start..self.location.position];
+                        if let Some(max) = self
+                            .options
+                            .max_name_length
+                            .filter(|&max| name.chars().count() > max)
+                        {
+                            return Err(LexerError {
+                                kind: LexerErrorKind::NameTooLong { max },
+                                location: start_location,
+                            });
+                        }
+
+                        if raw {
+                            TokenKind::Name(InternedStr::intern(name))
+                        } else if let Some(keyword) = keyword(name) {
+                            keyword
+                        } else {
+                            let name = InternedStr::intern(name);
+                            if self.options.reserved.contains(&name) {
+                                return Err(LexerError {
+                                    kind: LexerErrorKind::ReservedKeyword(name),
+                                    location: start_location,
+                                });
+                            }
+                            TokenKind::Name(name)
                         }
                     }
 
                     Some(c) if c.is_ascii_digit() => {
-                        let mut value = c.to_digit(10).unwrap() as u64;
-                        let base = if c == '0' {
+                        // overflow is only an error once the literal turns out not to be a float
+                        let mut value = Some(c.to_digit(10).unwrap() as u64);
+                        let prefix_base = if c == '0' {
                             match self.peek_char() {
-                                Some('x') => {
-                                    self.next_char();
-                                    16
-                                }
-                                Some('d') => {
-                                    self.next_char();
-                                    10
-                                }
-                                Some('o') => {
-                                    self.next_char();
-                                    8
-                                }
-                                Some('b') => {
-                                    self.next_char();
-                                    2
-                                }
-                                _ => 10,
+                                Some('x') => Some(16),
+                                Some('d') => Some(10),
+                                Some('o') => Some(8),
+                                Some('b') => Some(2),
+                                _ => None,
                             }
+                        } else {
+                            None
+                        };
+                        let base = if let Some(base) = prefix_base {
+                            self.next_char();
+                            base
                         } else {
                             10
                         };
 
-                        while let Some(c) = self.peek_char().filter(|c| c.is_ascii_alphanumeric()) {
+                        // a digit separator is only valid directly after a digit, and must be followed by one
+                        let mut previous_was_digit = prefix_base.is_none();
+                        let mut has_digits = prefix_base.is_none();
+                        let mut separator_location = None;
+                        while let Some(c) = self
+                            .peek_char()
+                            .filter(|&c| c.is_ascii_alphanumeric() || c == '_')
+                        {
+                            if c == '_' {
+                                if !previous_was_digit {
+                                    return Err(LexerError {
+                                        kind: LexerErrorKind::MisplacedDigitSeparator,
+                                        location: self.location,
+                                    });
+                                }
+                                separator_location = Some(self.location);
+                                previous_was_digit = false;
+                                self.next_char();
+                                continue;
+                            }
+
                             let digit = c.to_digit(base as _).ok_or(LexerError {
                                 kind: LexerErrorKind::DigitTooLarge { base },
                                 location: self.location,
                             })?;
 
                             self.next_char();
+                            previous_was_digit = true;
+                            has_digits = true;
 
                             value = value
-                                .checked_mul(base as _)
-                                .and_then(|value| value.checked_add(digit as _))
-                                .ok_or(LexerError {
-                                    kind: LexerErrorKind::IntegerTooLarge,
-                                    location: start_location,
-                                })?;
+                                .and_then(|value| value.checked_mul(base as _))
+                                .and_then(|value| value.checked_add(digit as _));
+                        }
+
+                        if let (false, Some(location)) = (previous_was_digit, separator_location) {
+                            return Err(LexerError {
+                                kind: LexerErrorKind::MisplacedDigitSeparator,
+                                location,
+                            });
                         }
+                        if !has_digits {
+                            return Err(LexerError {
+                                kind: LexerErrorKind::MissingDigits { base },
+                                location: start_location,
+                            });
+                        }
+
+                        // a `.` only starts a fraction if a digit follows it, so `1.method()` is still an integer
+                        let is_float = prefix_base.is_none() && {
+                            let mut rest = self.source[self.location.position..].chars();
+                            rest.next() == Some('.')
+                                && rest.next().is_some_and(|c| c.is_ascii_digit())
+                        };
+                        if is_float {
+                            self.next_char();
+                            while let Some(c) = self
+                                .peek_char()
+                                .filter(|&c| c.is_ascii_alphanumeric() || c == '_')
+                            {
+                                if c == '_' {
+                                    if !previous_was_digit {
+                                        return Err(LexerError {
+                                            kind: LexerErrorKind::MisplacedDigitSeparator,
+                                            location: self.location,
+                                        });
+                                    }
+                                    separator_location = Some(self.location);
+                                    previous_was_digit = false;
+                                } else if c.is_ascii_digit() {
+                                    previous_was_digit = true;
+                                } else {
+                                    return Err(LexerError {
+                                        kind: LexerErrorKind::DigitTooLarge { base: 10 },
+                                        location: self.location,
+                                    });
+                                }
+                                self.next_char();
+                            }
+
+                            if let (false, Some(location)) =
+                                (previous_was_digit, separator_location)
+                            {
+                                return Err(LexerError {
+                                    kind: LexerErrorKind::MisplacedDigitSeparator,
+                                    location,
+                                });
+                            }
+
+                            let digits = self.source
+                                [start_location.position..self.location.position]
+                                .replace('_', "");
+                            TokenKind::Float(digits.parse().unwrap())
+                        } else {
+                            let value = value.ok_or(LexerError {
+                                kind: LexerErrorKind::IntegerTooLarge,
+                                location: start_location,
+                            })?;
+                            let bits = self.options.max_int_bits;
+                            if bits < u64::BITS && value >> bits != 0 {
+                                return Err(LexerError {
+                                    kind: LexerErrorKind::IntegerTooWide { bits },
+                                    location: start_location,
+                                });
+                            }
 
-                        TokenKind::Integer(value)
+                            TokenKind::Integer(value)
+                        }
                     }
 
-                    Some(c) if c.is_whitespace() => continue,
+                    Some(c)
+                        if c.is_ascii_whitespace()
+                            || (!self.options.ascii_whitespace_only && c.is_whitespace()) =>
+                    {
+                        continue;
+                    }
                     Some(c) => {
                         return Err(LexerError {
                             kind: LexerErrorKind::UnexpectedChar(c),
@@ -231,3 +1175,28 @@ impl<'source> Lexer<'source> {
         }
     }
 }
+
+pub struct Tokens<'source> {
+    lexer: Lexer<'source>,
+    finished: bool,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.lexer.next_raw_token();
+        self.finished = matches!(
+            token,
+            Err(_)
+                | Ok(Token {
+                    kind: TokenKind::EOF,
+                    ..
+                })
+        );
+        Some(token)
+    }
+}
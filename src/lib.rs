@@ -1,7 +1,23 @@
 #![deny(rust_2018_idioms, rust_2024_compatibility)]
 
+pub mod analysis;
 pub mod ast;
+pub mod call_graph;
+pub mod cfg;
+pub mod const_eval;
+pub mod diff;
+#[cfg(feature = "fancy-errors")]
+pub mod fancy_errors;
+pub mod fold;
 pub mod interning;
+pub mod interpreter;
+pub mod json;
 pub mod lexer;
+pub mod line_index;
+pub mod lints;
+pub mod mangling;
+pub mod normalize;
 pub mod parsing;
 pub mod pretty_printing;
+pub mod resolve;
+pub mod visitor;
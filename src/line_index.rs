@@ -0,0 +1,70 @@
+use std::ops::Range;
+
+/// A position as the language server protocol counts it, 0 based with `character` in UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LspPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+/// Converts between byte offsets and `LspPosition`s, built once so each conversion only scans a single line
+#[derive(Debug, Clone)]
+pub struct LineIndex<'source> {
+    source: &'source str,
+    line_starts: Vec<usize>,
+}
+
+impl<'source> LineIndex<'source> {
+    pub fn new(source: &'source str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(position, _)| position + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The position of the byte offset `position`, which must be on a character boundary
+    pub fn to_lsp_position(&self, position: usize) -> LspPosition {
+        debug_assert!(self.source.is_char_boundary(position));
+        let line = self.line_starts.partition_point(|&start| start <= position) - 1;
+        let character = self.source[self.line_starts[line]..position]
+            .encode_utf16()
+            .count();
+        LspPosition {
+            line: line as _,
+            character: character as _,
+        }
+    }
+
+    pub fn to_lsp_range(&self, range: Range<usize>) -> LspRange {
+        LspRange {
+            start: self.to_lsp_position(range.start),
+            end: self.to_lsp_position(range.end),
+        }
+    }
+
+    /// The byte offset of a position, a line or character past the end is clamped to the end of the source or line,
+    /// and a character in the middle of a surrogate pair to the start of it
+    pub fn offset(&self, line: u32, character: u32) -> usize {
+        let Some(&line_start) = self.line_starts.get(line as usize) else {
+            return self.source.len();
+        };
+
+        let mut utf16_column = 0;
+        for (position, c) in self.source[line_start..].char_indices() {
+            utf16_column += c.len_utf16();
+            if c == '\n' || utf16_column > character as usize {
+                return line_start + position;
+            }
+        }
+        self.source.len()
+    }
+}
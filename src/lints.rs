@@ -0,0 +1,151 @@
+use crate::{
+    ast::{Ast, AstKind},
+    call_graph::call_graph,
+    interning::InternedStr,
+    lexer::{Lexer, LexerError, Location, TokenKind},
+    resolve::{global_declarations, DeclId},
+};
+use derive_more::derive::Display;
+use rustc_hash::FxHashSet;
+
+/// The number of spaces each level of brace nesting is expected to be indented by
+pub const INDENT_WIDTH: usize = 4;
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    #[display("Expected indentation of {expected} but got {found}")]
+    MisindentedLine { expected: usize, found: usize },
+    #[display("Decimal integer literal has leading zeros, which do not make it octal")]
+    LeadingZeros,
+    #[display("Function '{name}' is never called from an entry point")]
+    DeadFunction { name: InternedStr },
+}
+
+impl WarningKind {
+    /// The name of the variant, which stays the same when the message is reworded
+    pub fn code(&self) -> &'static str {
+        match *self {
+            WarningKind::MisindentedLine { .. } => "MisindentedLine",
+            WarningKind::LeadingZeros => "LeadingZeros",
+            WarningKind::DeadFunction { .. } => "DeadFunction",
+        }
+    }
+}
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+#[display("{location}: {kind}")]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub location: Location,
+}
+
+/// Checks that the first token of every line is indented by `INDENT_WIDTH` for each enclosing brace
+///
+/// Lines that continue inside parentheses or brackets have no expected indentation and are not checked
+pub fn check_brace_indent(filepath: InternedStr, source: &str) -> Result<Vec<Warning>, LexerError> {
+    let lexer = &mut Lexer::new(filepath, source);
+    let mut warnings = vec![];
+    let mut brace_depth = 0usize;
+    let mut delimiter_depth = 0usize;
+    let mut previous_line = None;
+    loop {
+        let token = lexer.next_token()?;
+        if let TokenKind::EOF = token.kind {
+            break;
+        }
+
+        if previous_line != Some(token.location.line) && delimiter_depth == 0 {
+            // a closing brace is indented to the level of the line that opened it
+            let depth = if let TokenKind::CloseBrace = token.kind {
+                brace_depth.saturating_sub(1)
+            } else {
+                brace_depth
+            };
+            let expected = depth * INDENT_WIDTH;
+            let found = token.location.column.get() - 1;
+            if found != expected {
+                warnings.push(Warning {
+                    kind: WarningKind::MisindentedLine { expected, found },
+                    location: token.location,
+                });
+            }
+        }
+        previous_line = Some(token.location.line);
+
+        match token.kind {
+            TokenKind::OpenBrace => brace_depth += 1,
+            TokenKind::CloseBrace => brace_depth = brace_depth.saturating_sub(1),
+            TokenKind::OpenParenthesis | TokenKind::OpenBracket => delimiter_depth += 1,
+            TokenKind::CloseParenthesis | TokenKind::CloseBracket => {
+                delimiter_depth = delimiter_depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+    Ok(warnings)
+}
+
+/// Flags decimal integer literals like `007` that start with a zero but are not just `0`
+///
+/// Literals with a base prefix like `0o7` are exempt, as the zero is part of the prefix
+pub fn check_leading_zeros(
+    filepath: InternedStr,
+    source: &str,
+) -> Result<Vec<Warning>, LexerError> {
+    let lexer = &mut Lexer::new(filepath, source);
+    let mut warnings = vec![];
+    loop {
+        let token = lexer.next_token()?;
+        match token.kind {
+            TokenKind::EOF => break,
+            TokenKind::Integer(_) => {
+                let text = &source[token.location.position..lexer.location().position];
+                if text.starts_with('0')
+                    && text[1..].starts_with(|c: char| c.is_ascii_digit() || c == '_')
+                {
+                    warnings.push(Warning {
+                        kind: WarningKind::LeadingZeros,
+                        location: token.location,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(warnings)
+}
+
+/// Reports every global function that cannot be reached from any of the functions named in `entry_points`
+///
+/// Entry points that are not defined are ignored, and nothing is reported if none of them are defined,
+/// as every function would be unreachable
+pub fn check_dead_functions(asts: &[Ast], entry_points: &[InternedStr]) -> Vec<Warning> {
+    let declarations = global_declarations(asts);
+    let graph = call_graph(asts);
+    let mut live = FxHashSet::default();
+    for entry_point in entry_points {
+        if let Some(&entry_point) = declarations.get(entry_point) {
+            live.extend(graph.reachable_from(entry_point));
+        }
+    }
+    if live.is_empty() {
+        return vec![];
+    }
+
+    let mut warnings = vec![];
+    for (index, ast) in asts.iter().enumerate() {
+        let AstKind::Function { ref name, .. } = ast.kind else {
+            continue;
+        };
+        let TokenKind::Name(name_str) = name.kind else {
+            unreachable!();
+        };
+        if !live.contains(&DeclId(index)) {
+            warnings.push(Warning {
+                kind: WarningKind::DeadFunction { name: name_str },
+                location: name.location,
+            });
+        }
+    }
+    warnings
+}
@@ -0,0 +1,22 @@
+use crate::interning::InternedStr;
+
+/// The flat symbol name of the item `name` nested inside the items `scope`, outermost first
+///
+/// Each path segment has its `_`s written as `_0`, and the segments are joined with `__`, so `double` inside `foo`
+/// is `foo__double` and `my_fn` is `my_0fn`. Every `_` in the result starts either a `_0` or a `__`, so the path can
+/// always be read back and two different paths never mangle to the same symbol
+pub fn mangle(name: InternedStr, scope: &[InternedStr]) -> String {
+    let mut symbol = String::new();
+    for (i, segment) in scope.iter().chain([&name]).enumerate() {
+        if i > 0 {
+            symbol.push_str("__");
+        }
+        for c in segment.to_str().chars() {
+            match c {
+                '_' => symbol.push_str("_0"),
+                c => symbol.push(c),
+            }
+        }
+    }
+    symbol
+}
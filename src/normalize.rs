@@ -0,0 +1,524 @@
+use crate::{
+    ast::{
+        Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind, BinaryOperator,
+        UnaryOperator,
+    },
+    const_eval::{eval_const, ConstEnv},
+    fold::{fold_expression, Folder},
+    interpreter::Value,
+    pretty_printing::pretty_print_ast_expression,
+};
+use std::cmp::Ordering;
+
+/// Rewrites the tree into a canonical form, so trees that only differ trivially are `structurally_eq` afterwards
+///
+/// Parentheses are removed, operators on literals are folded unless that would fail, and the operands of
+/// commutative operators are put in a fixed order, which does not preserve the order calls are evaluated in.
+/// Blocks are kept, as a block never has the value of its last statement
+pub fn normalize(mut ast: Ast) -> Ast {
+    normalize_ast(&mut ast);
+    ast
+}
+
+pub fn normalize_expression(mut expression: AstExpression) -> AstExpression {
+    normalize_expression_in_place(&mut expression);
+    expression
+}
+
+fn normalize_ast(ast: &mut Ast) {
+    match ast.kind {
+        AstKind::Expression(ref mut expression) | AstKind::Return { ref mut expression } => {
+            normalize_expression_in_place(expression);
+        }
+        AstKind::Let {
+            ref mut pattern,
+            equals: _,
+            ref mut value,
+        } => {
+            normalize_pattern(pattern);
+            normalize_expression_in_place(value);
+        }
+        AstKind::Function {
+            doc: _,
+            attributes: _,
+            name: _,
+            generics: _,
+            ref mut arguments,
+            ref mut return_type,
+            ref mut where_clause,
+            ref mut body,
+        } => {
+            arguments.iter_mut().for_each(normalize_pattern);
+            if let Some(return_type) = return_type {
+                normalize_expression_in_place(return_type);
+            }
+            for (_, bound) in where_clause {
+                normalize_expression_in_place(bound);
+            }
+            normalize_expression_in_place(body);
+        }
+        AstKind::ExternFunction {
+            doc: _,
+            attributes: _,
+            name: _,
+            ref mut arguments,
+            ref mut return_type,
+        } => {
+            arguments.iter_mut().for_each(normalize_pattern);
+            if let Some(return_type) = return_type {
+                normalize_expression_in_place(return_type);
+            }
+        }
+        AstKind::Struct {
+            doc: _,
+            attributes: _,
+            name: _,
+            ref mut fields,
+        } => {
+            for (_, typ) in fields {
+                normalize_expression_in_place(typ);
+            }
+        }
+        AstKind::Enum {
+            doc: _,
+            attributes: _,
+            name: _,
+            ref mut variants,
+        } => {
+            for (_, payload) in variants {
+                payload.iter_mut().for_each(normalize_expression_in_place);
+            }
+        }
+        AstKind::For {
+            ref mut pattern,
+            ref mut iterable,
+            ref mut body,
+        } => {
+            normalize_pattern(pattern);
+            normalize_expression_in_place(iterable);
+            normalize_expression_in_place(body);
+        }
+        AstKind::Assignment {
+            ref mut target,
+            equals: _,
+            ref mut value,
+        } => {
+            normalize_expression_in_place(target);
+            normalize_expression_in_place(value);
+        }
+    }
+}
+
+fn normalize_pattern(pattern: &mut AstPattern) {
+    match pattern.kind {
+        AstPatternKind::Let {
+            name_token: _,
+            ref mut typ,
+        } => {
+            if let Some(typ) = typ {
+                normalize_expression_in_place(typ);
+            }
+        }
+        AstPatternKind::Wildcard
+        | AstPatternKind::Integer(_)
+        | AstPatternKind::Range { .. }
+        | AstPatternKind::Boolean(_) => {}
+        AstPatternKind::Tuple(ref mut elements) => elements.iter_mut().for_each(normalize_pattern),
+        AstPatternKind::Binding {
+            name: _,
+            ref mut pattern,
+        } => normalize_pattern(pattern),
+    }
+}
+
+fn normalize_expression_in_place(expression: &mut AstExpression) {
+    match expression.kind {
+        AstExpressionKind::Name(_)
+        | AstExpressionKind::Integer(_)
+        | AstExpressionKind::Float(_)
+        | AstExpressionKind::String(_)
+        | AstExpressionKind::Char(_)
+        | AstExpressionKind::Boolean(_)
+        | AstExpressionKind::Unit => {}
+        AstExpressionKind::Unary {
+            operator: _,
+            ref mut operand,
+        } => normalize_expression_in_place(operand),
+        AstExpressionKind::Binary {
+            ref mut left,
+            ref operator,
+            ref mut right,
+        } => {
+            normalize_expression_in_place(left);
+            normalize_expression_in_place(right);
+            if is_commutative(operator) && compare_operands(left, right).is_gt() {
+                std::mem::swap(left, right);
+            }
+        }
+        AstExpressionKind::Range {
+            ref mut start,
+            ref mut end,
+            inclusive: _,
+        } => {
+            if let Some(start) = start {
+                normalize_expression_in_place(start);
+            }
+            if let Some(end) = end {
+                normalize_expression_in_place(end);
+            }
+        }
+        AstExpressionKind::Array {
+            ref mut elements,
+            close_bracket: _,
+        } => elements.iter_mut().for_each(normalize_expression_in_place),
+        AstExpressionKind::ArrayRepeat {
+            ref mut value,
+            ref mut count,
+            close_bracket: _,
+        } => {
+            normalize_expression_in_place(value);
+            normalize_expression_in_place(count);
+        }
+        AstExpressionKind::Block {
+            ref mut statements,
+            close_brace: _,
+        } => statements.iter_mut().for_each(normalize_ast),
+        AstExpressionKind::If {
+            ref mut condition,
+            ref mut then_block,
+            ref mut else_block,
+        } => {
+            normalize_expression_in_place(condition);
+            normalize_expression_in_place(then_block);
+            if let Some(else_block) = else_block {
+                normalize_expression_in_place(else_block);
+            }
+        }
+        AstExpressionKind::Match {
+            ref mut scrutinee,
+            ref mut arms,
+            close_brace: _,
+        } => {
+            normalize_expression_in_place(scrutinee);
+            for (pattern, value) in arms {
+                normalize_pattern(pattern);
+                normalize_expression_in_place(value);
+            }
+        }
+        AstExpressionKind::Call {
+            ref mut operand,
+            ref mut arguments,
+            commas: _,
+            close_parenthesis: _,
+        } => {
+            normalize_expression_in_place(operand);
+            arguments.iter_mut().for_each(normalize_expression_in_place);
+        }
+        AstExpressionKind::StructLiteral {
+            name: _,
+            ref mut fields,
+            close_brace: _,
+        } => {
+            for (_, value) in fields {
+                normalize_expression_in_place(value);
+            }
+        }
+        AstExpressionKind::FieldAccess {
+            ref mut operand,
+            name_token: _,
+        } => normalize_expression_in_place(operand),
+        AstExpressionKind::Index {
+            ref mut operand,
+            ref mut index,
+            close_bracket: _,
+        } => {
+            normalize_expression_in_place(operand);
+            normalize_expression_in_place(index);
+        }
+        AstExpressionKind::Closure {
+            ref mut arguments,
+            ref mut statements,
+            close_brace: _,
+        } => {
+            arguments.iter_mut().for_each(normalize_pattern);
+            statements.iter_mut().for_each(normalize_ast);
+        }
+        AstExpressionKind::Paren { .. } => {
+            // the placeholder is dropped along with the parentheses straight away
+            let AstExpressionKind::Paren { inner, .. } =
+                std::mem::replace(&mut expression.kind, AstExpressionKind::Unit)
+            else {
+                unreachable!();
+            };
+            *expression = *inner;
+            return normalize_expression_in_place(expression);
+        }
+    }
+
+    if let AstExpressionKind::Unary { .. } | AstExpressionKind::Binary { .. } = expression.kind {
+        fold_constant(expression);
+    }
+}
+
+/// Folds operators on literals like `normalize` does, without removing parentheses or reordering operands
+pub struct ConstantFolder;
+
+impl Folder for ConstantFolder {
+    fn fold_expression(&mut self, expression: AstExpression) -> AstExpression {
+        let mut expression = fold_expression(self, expression);
+        fold_constant(&mut expression);
+        expression
+    }
+}
+
+/// Replaces an operator whose operands are all literals with its value
+fn fold_constant(expression: &mut AstExpression) {
+    let operands_are_literals = match expression.kind {
+        AstExpressionKind::Unary { ref operand, .. } => is_literal(operand),
+        AstExpressionKind::Binary {
+            ref left,
+            ref right,
+            ..
+        } => is_literal(left) && is_literal(right),
+        _ => false,
+    };
+    if !operands_are_literals {
+        return;
+    }
+    let Ok(value) = eval_const(expression, &ConstEnv::default()) else {
+        return;
+    };
+
+    let location = expression.location;
+    expression.kind = match value {
+        Value::Integer(value) if value < 0 => AstExpressionKind::Unary {
+            operator: UnaryOperator::Negate,
+            operand: Box::new(AstExpression {
+                kind: AstExpressionKind::Integer(value.unsigned_abs()),
+                location,
+            }),
+        },
+        Value::Integer(value) => AstExpressionKind::Integer(value as u64),
+        Value::Bool(value) => AstExpressionKind::Boolean(value),
+        Value::Unit => AstExpressionKind::Unit,
+        Value::Builtin(_) => return,
+    };
+}
+
+/// Whether the expression is a literal, where a negative integer is written as `-` applied to a literal
+fn is_literal(expression: &AstExpression) -> bool {
+    match expression.kind {
+        AstExpressionKind::Integer(_) | AstExpressionKind::Boolean(_) | AstExpressionKind::Unit => {
+            true
+        }
+        AstExpressionKind::Unary {
+            operator: UnaryOperator::Negate,
+            ref operand,
+        } => matches!(operand.kind, AstExpressionKind::Integer(_)),
+        _ => false,
+    }
+}
+
+fn is_commutative(operator: &BinaryOperator) -> bool {
+    match *operator {
+        BinaryOperator::Add
+        | BinaryOperator::Multiply
+        | BinaryOperator::Equal
+        | BinaryOperator::NotEqual => true,
+        BinaryOperator::Subtract
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo
+        | BinaryOperator::Less
+        | BinaryOperator::Greater
+        | BinaryOperator::LessEqual
+        | BinaryOperator::GreaterEqual
+        | BinaryOperator::And
+        | BinaryOperator::Or => false,
+    }
+}
+
+/// Orders operands structurally, ignoring locations like `structurally_eq` does
+///
+/// The comparison stops at the first difference, so ordering the operands at every level of a long chain
+/// does not look at the whole chain each time
+fn compare_operands(a: &AstExpression, b: &AstExpression) -> Ordering {
+    use AstExpressionKind as Kind;
+    match (&a.kind, &b.kind) {
+        (Kind::Name(a), Kind::Name(b)) | (Kind::String(a), Kind::String(b)) => {
+            a.to_str().cmp(b.to_str())
+        }
+        (Kind::Integer(a), Kind::Integer(b)) => a.cmp(b),
+        (Kind::Float(a), Kind::Float(b)) => a.total_cmp(b),
+        (Kind::Char(a), Kind::Char(b)) => a.cmp(b),
+        (Kind::Boolean(a), Kind::Boolean(b)) => a.cmp(b),
+        (Kind::Unit, Kind::Unit) => Ordering::Equal,
+        (
+            Kind::Unary {
+                operator: a_operator,
+                operand: a,
+            },
+            Kind::Unary {
+                operator: b_operator,
+                operand: b,
+            },
+        ) => (a_operator.to_string().cmp(&b_operator.to_string()))
+            .then_with(|| compare_operands(a, b)),
+        (
+            Kind::Binary {
+                left: a_left,
+                operator: a_operator,
+                right: a_right,
+            },
+            Kind::Binary {
+                left: b_left,
+                operator: b_operator,
+                right: b_right,
+            },
+        ) => compare_operands(a_left, b_left)
+            .then_with(|| a_operator.to_string().cmp(&b_operator.to_string()))
+            .then_with(|| compare_operands(a_right, b_right)),
+        (
+            Kind::Range {
+                start: a_start,
+                end: a_end,
+                inclusive: a_inclusive,
+            },
+            Kind::Range {
+                start: b_start,
+                end: b_end,
+                inclusive: b_inclusive,
+            },
+        ) => compare_optional_operands(a_start.as_deref(), b_start.as_deref())
+            .then_with(|| compare_optional_operands(a_end.as_deref(), b_end.as_deref()))
+            .then_with(|| a_inclusive.cmp(b_inclusive)),
+        (Kind::Array { elements: a, .. }, Kind::Array { elements: b, .. }) => {
+            compare_operand_lists(a, b)
+        }
+        (
+            Kind::ArrayRepeat {
+                value: a_value,
+                count: a_count,
+                ..
+            },
+            Kind::ArrayRepeat {
+                value: b_value,
+                count: b_count,
+                ..
+            },
+        ) => compare_operands(a_value, b_value).then_with(|| compare_operands(a_count, b_count)),
+        (
+            Kind::Call {
+                operand: a_operand,
+                arguments: a_arguments,
+                ..
+            },
+            Kind::Call {
+                operand: b_operand,
+                arguments: b_arguments,
+                ..
+            },
+        ) => compare_operands(a_operand, b_operand)
+            .then_with(|| compare_operand_lists(a_arguments, b_arguments)),
+        (
+            Kind::StructLiteral {
+                name: a_name,
+                fields: a_fields,
+                ..
+            },
+            Kind::StructLiteral {
+                name: b_name,
+                fields: b_fields,
+                ..
+            },
+        ) => (a_name.kind.to_string().cmp(&b_name.kind.to_string())).then_with(|| {
+            a_fields
+                .iter()
+                .zip(b_fields)
+                .map(|((a_name, a), (b_name, b))| {
+                    (a_name.kind.to_string().cmp(&b_name.kind.to_string()))
+                        .then_with(|| compare_operands(a, b))
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a_fields.len().cmp(&b_fields.len()))
+        }),
+        (
+            Kind::FieldAccess {
+                operand: a_operand,
+                name_token: a_name,
+            },
+            Kind::FieldAccess {
+                operand: b_operand,
+                name_token: b_name,
+            },
+        ) => compare_operands(a_operand, b_operand)
+            .then_with(|| a_name.kind.to_string().cmp(&b_name.kind.to_string())),
+        (
+            Kind::Index {
+                operand: a_operand,
+                index: a_index,
+                ..
+            },
+            Kind::Index {
+                operand: b_operand,
+                index: b_index,
+                ..
+            },
+        ) => {
+            compare_operands(a_operand, b_operand).then_with(|| compare_operands(a_index, b_index))
+        }
+        (Kind::Paren { inner: a, .. }, Kind::Paren { inner: b, .. }) => compare_operands(a, b),
+        // blocks, ifs, matches and closures hold statements and patterns, which are rare in operands,
+        // so two of the same kind are compared by how they are printed
+        (a_kind, b_kind) => variant_rank(a_kind)
+            .cmp(&variant_rank(b_kind))
+            .then_with(|| printed(a).cmp(&printed(b))),
+    }
+}
+
+fn compare_optional_operands(a: Option<&AstExpression>, b: Option<&AstExpression>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => compare_operands(a, b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+fn compare_operand_lists(a: &[AstExpression], b: &[AstExpression]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| compare_operands(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn variant_rank(kind: &AstExpressionKind) -> u8 {
+    match *kind {
+        AstExpressionKind::Name(_) => 0,
+        AstExpressionKind::Integer(_) => 1,
+        AstExpressionKind::Float(_) => 2,
+        AstExpressionKind::String(_) => 3,
+        AstExpressionKind::Char(_) => 4,
+        AstExpressionKind::Boolean(_) => 5,
+        AstExpressionKind::Unit => 6,
+        AstExpressionKind::Unary { .. } => 7,
+        AstExpressionKind::Binary { .. } => 8,
+        AstExpressionKind::Range { .. } => 9,
+        AstExpressionKind::Array { .. } => 10,
+        AstExpressionKind::ArrayRepeat { .. } => 11,
+        AstExpressionKind::Block { .. } => 12,
+        AstExpressionKind::If { .. } => 13,
+        AstExpressionKind::Match { .. } => 14,
+        AstExpressionKind::Call { .. } => 15,
+        AstExpressionKind::StructLiteral { .. } => 16,
+        AstExpressionKind::FieldAccess { .. } => 17,
+        AstExpressionKind::Index { .. } => 18,
+        AstExpressionKind::Closure { .. } => 19,
+        AstExpressionKind::Paren { .. } => 20,
+    }
+}
+
+fn printed(expression: &AstExpression) -> Vec<u8> {
+    let mut printed = vec![];
+    pretty_print_ast_expression(expression, 0, &mut printed)
+        .expect("writing to a vec should never fail");
+    printed
+}
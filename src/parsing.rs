@@ -1,9 +1,10 @@
 use crate::{
     ast::{
-        Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind, BinaryOperator,
+        Ast, AstAttribute, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind,
+        BinaryOperator, Module, UnaryOperator,
     },
     interning::InternedStr,
-    lexer::{Lexer, LexerError, LexerErrorKind, Location, Token, TokenKind},
+    lexer::{Lexer, LexerError, LexerErrorKind, LexerOptions, Location, Token, TokenKind},
 };
 use std::num::NonZero;
 use thiserror::Error;
@@ -20,6 +21,82 @@ pub enum ParseErrorKind {
     ExpectedExpression(TokenKind),
     #[error("Expected pattern but got '{0}'")]
     ExpectedPattern(TokenKind),
+    #[error("Expected type but got '{0}'")]
+    ExpectedType(TokenKind),
+    #[error("Cannot assign to this expression")]
+    InvalidAssignmentTarget,
+    #[error("Inclusive range must have an end")]
+    InclusiveRangeWithoutEnd,
+    #[error("List has more than {max} elements")]
+    TooManyArguments { max: usize },
+    #[error("`@` bindings are not allowed in `let` statements")]
+    BindingInLet,
+    #[error("`let` patterns have to match every value")]
+    RefutablePatternInLet,
+    #[error("The file '{0}' is part of the module more than once")]
+    DuplicateFile(InternedStr),
+}
+
+impl ParseErrorKind {
+    /// A longer description of the error and how it is usually fixed
+    pub fn explanation(&self) -> &'static str {
+        match *self {
+            ParseErrorKind::LexerError(ref kind) => kind.explanation(),
+            ParseErrorKind::UnexpectedToken(_) => {
+                "The parser needed a specific token here, like a `;` after a statement, \
+                 a `)` closing an argument list, or the `= value` of a `let` binding. \
+                 Check just before this location for something missing."
+            }
+            ParseErrorKind::ExpectedGlobalItem(_) => {
+                "Only functions, `extern fn` declarations, structs and enums can appear at the top level of a file, \
+                 each optionally preceded by a `/** doc comment */` and `#[attributes]`. \
+                 Statements like `let` have to be placed inside a function body, \
+                 and `//!` module documentation has to come before the first item."
+            }
+            ParseErrorKind::ExpectedExpression(_) => {
+                "A value was needed here, such as a name, an integer, a call, \
+                 a parenthesised expression or a `{ ... }` block. \
+                 This often means an operator is missing its right operand."
+            }
+            ParseErrorKind::ExpectedPattern(_) => {
+                "A binding was needed here. Bindings are written as `let name` or `let name: type`, \
+                 and the `let` can be left out for function and closure arguments. \
+                 Without the `let`, a pattern can also be `_`, an integer, a range of integers like `1..=10`, \
+                 `true` or `false`, and `(a, b)` destructures a tuple, also after a `let`."
+            }
+            ParseErrorKind::ExpectedType(_) => {
+                "A type was needed here. Types are names like `int`, \
+                 applications like `list(int)`, or a type in parentheses."
+            }
+            ParseErrorKind::InvalidAssignmentTarget => {
+                "Only places that hold a value, like a variable name, can be on the left of `=`. \
+                 To compare two values use `==` instead."
+            }
+            ParseErrorKind::InclusiveRangeWithoutEnd => {
+                "A `..=` range includes its end, so the end cannot be left out. \
+                 Use `start..` for a range without an end."
+            }
+            ParseErrorKind::TooManyArguments { .. } => {
+                "The parser was configured with a maximum number of arguments, parameters and elements \
+                 in a single list, which this list exceeds. \
+                 Split the list up, or raise `ParserOptions::max_arguments`."
+            }
+            ParseErrorKind::BindingInLet => {
+                "A `name @ pattern` binding gives a name to a value that is also matched against a pattern. \
+                 A `let` statement already names the whole value, so the `@` is not needed there."
+            }
+            ParseErrorKind::RefutablePatternInLet => {
+                "A `let` has nowhere to go when its pattern does not match, \
+                 so integer, range and boolean patterns can only be used in a `match`. \
+                 Bind the value to a name and compare it instead."
+            }
+            ParseErrorKind::DuplicateFile(_) => {
+                "Each file of a module is looked up by its filepath, \
+                 so two files with the same filepath cannot be parsed into one module. \
+                 Remove the repeated file or give it a different filepath."
+            }
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -32,23 +109,142 @@ pub struct ParseError {
 impl From<LexerError> for ParseError {
     fn from(error: LexerError) -> Self {
         Self {
-            kind: ParseErrorKind::LexerError(error.kind),
+            kind: match error.kind {
+                LexerErrorKind::UnexpectedToken(kind) => ParseErrorKind::UnexpectedToken(kind),
+                kind => ParseErrorKind::LexerError(kind),
+            },
             location: error.location,
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    pub lexer: LexerOptions,
+    /// Whether a block directly after a call's parentheses is passed as a trailing closure, defaults to `true`
+    pub trailing_closures: bool,
+    /// Whether `Name {` starts a struct literal, defaults to `true`, disabled in conditions where the `{` starts a block
+    pub struct_literals: bool,
+    /// Whether a comma is allowed before the closing parenthesis of a list, defaults to `true`
+    pub trailing_commas: bool,
+    /// Whether parenthesised expressions and types are kept as `AstExpressionKind::Paren`, defaults to `false`
+    pub preserve_parens: bool,
+    /// The most arguments, parameters or elements a single list can have, defaults to 65536
+    pub max_arguments: usize,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            lexer: LexerOptions::default(),
+            trailing_closures: true,
+            struct_literals: true,
+            trailing_commas: true,
+            preserve_parens: false,
+            max_arguments: 1 << 16,
+        }
+    }
+}
+
 pub fn parse(filepath: InternedStr, source: &str) -> Result<Vec<Ast>, ParseError> {
-    let lexer = &mut Lexer::new(filepath, source);
+    parse_with_options(filepath, source, &ParserOptions::default())
+}
+
+pub fn parse_with_options(
+    filepath: InternedStr,
+    source: &str,
+    options: &ParserOptions,
+) -> Result<Vec<Ast>, ParseError> {
+    let (_, statements) = parse_file(filepath, source, options)?;
+    Ok(statements)
+}
+
+/// Parses a file along with the `//!` lines at the start of it
+///
+/// A `//!` anywhere after the first item is not module documentation and is a parse error
+pub fn parse_file(
+    filepath: InternedStr,
+    source: &str,
+    options: &ParserOptions,
+) -> Result<(Option<InternedStr>, Vec<Ast>), ParseError> {
+    let lexer = &mut Lexer::new_with_options(filepath, source, &options.lexer);
+    parse_file_with(lexer, options)
+}
+
+fn parse_file_with(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<(Option<InternedStr>, Vec<Ast>), ParseError> {
+    let mut doc_lines = vec![];
+    while let TokenKind::InnerDocComment(line) = lexer.peek_token()?.kind {
+        lexer.next_token()?;
+        doc_lines.push(line.to_str().strip_prefix(' ').unwrap_or(line.to_str()));
+    }
+    let module_doc = (!doc_lines.is_empty()).then(|| InternedStr::intern(&doc_lines.join("\n")));
+
     let mut statements = vec![];
     while !matches!(lexer.peek_token()?.kind, TokenKind::EOF) {
-        statements.push(parse_global(lexer)?);
+        statements.push(parse_global(lexer, options)?);
+    }
+    Ok((module_doc, statements))
+}
+
+/// Parses the source along with every token the parser consumed, including punctuation that is not stored in the ast
+pub fn parse_with_tokens(
+    filepath: InternedStr,
+    source: &str,
+) -> Result<(Vec<Ast>, Vec<Token>), ParseError> {
+    let options = &ParserOptions::default();
+    let lexer = &mut Lexer::new_with_options(filepath, source, &options.lexer);
+    lexer.record_tokens();
+    let (_, asts) = parse_file_with(lexer, options)?;
+    // the loop only peeked at `EOF`, so it is consumed to end the stream with it
+    lexer.next_token()?;
+    Ok((asts, lexer.recorded_tokens()))
+}
+
+/// Parses every file, reporting the first error of each file that fails to parse,
+/// and a `DuplicateFile` error at the start of any file whose filepath was already given
+pub fn parse_module<'source>(
+    files: impl IntoIterator<Item = (InternedStr, &'source str)>,
+) -> Result<Module, Vec<ParseError>> {
+    let mut module = Module {
+        items: vec![],
+        files: Default::default(),
+        module_doc: None,
+    };
+    let mut docs = vec![];
+    let mut errors = vec![];
+    for (filepath, source) in files {
+        if module.files.contains_key(&filepath) {
+            errors.push(ParseError {
+                kind: ParseErrorKind::DuplicateFile(filepath),
+                location: Lexer::new(filepath, source).location(),
+            });
+            continue;
+        }
+        match parse_file(filepath, source, &ParserOptions::default()) {
+            Ok((doc, items)) => {
+                docs.extend(doc.map(InternedStr::to_str));
+                let start = module.items.len();
+                module.items.extend(items);
+                module.files.insert(filepath, start..module.items.len());
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+    module.module_doc = (!docs.is_empty()).then(|| InternedStr::intern(&docs.join("\n\n")));
+    if errors.is_empty() {
+        Ok(module)
+    } else {
+        Err(errors)
     }
-    Ok(statements)
 }
 
+// `expr_2021` keeps the fragment the same on edition 2024, a plain `expr` trips the
+// `edition_2024_expr_fragment_specifier` lint that `deny(rust_2024_compatibility)` in lib.rs makes an error
 macro_rules! expect_token {
-    ($lexer:expr, $pattern:pat) => {
+    ($lexer:expr_2021, $pattern:pat) => {
         match $lexer.next_token() {
             Ok(token @ Token { kind: $pattern, .. }) => Ok(token),
             Ok(token) => Err(ParseError {
@@ -60,12 +256,98 @@ macro_rules! expect_token {
     };
 }
 
-pub fn parse_global(lexer: &mut Lexer<'_>) -> Result<Ast, ParseError> {
+/// Parses exactly one expression, like `1 + 2`, with nothing after it
+pub fn parse_expression_str(
+    filepath: InternedStr,
+    source: &str,
+) -> Result<AstExpression, ParseError> {
+    let options = &ParserOptions::default();
+    let lexer = &mut Lexer::new_with_options(filepath, source, &options.lexer);
+    let expression = parse_expression(lexer, options)?;
+    expect_token!(lexer, TokenKind::EOF)?;
+    Ok(expression)
+}
+
+/// Parses exactly one statement, like `let x = 1;`, with nothing after it
+pub fn parse_statement_str(filepath: InternedStr, source: &str) -> Result<Ast, ParseError> {
+    let options = &ParserOptions::default();
+    let lexer = &mut Lexer::new_with_options(filepath, source, &options.lexer);
+    let statement = parse_statement(lexer, options)?;
+    expect_token!(lexer, TokenKind::EOF)?;
+    Ok(statement)
+}
+
+/// Errors if a list that already has `length` elements gets another one, going over `ParserOptions::max_arguments`
+fn check_list_length(
+    lexer: &Lexer<'_>,
+    options: &ParserOptions,
+    length: usize,
+) -> Result<(), ParseError> {
+    if length < options.max_arguments {
+        return Ok(());
+    }
+    Err(ParseError {
+        kind: ParseErrorKind::TooManyArguments {
+            max: options.max_arguments,
+        },
+        location: lexer.peek_token()?.location,
+    })
+}
+
+/// Consumes the comma after an element of a list closed by `close`, returning its location, unless the list ends here
+fn parse_list_separator(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    close: &TokenKind,
+) -> Result<Option<Location>, ParseError> {
+    if lexer.peek_token()?.kind == *close {
+        return Ok(None);
+    }
+    let comma = expect_token!(lexer, TokenKind::Comma)?.location;
+    if !options.trailing_commas {
+        let Token { kind, location } = lexer.peek_token()?;
+        if kind == *close {
+            return Err(ParseError {
+                kind: ParseErrorKind::UnexpectedToken(kind),
+                location,
+            });
+        }
+    }
+    Ok(Some(comma))
+}
+
+pub fn parse_global(lexer: &mut Lexer<'_>, options: &ParserOptions) -> Result<Ast, ParseError> {
+    let doc = parse_doc_comments(lexer)?;
+    parse_item(lexer, options, doc)
+}
+
+/// Parses an item whose doc comments have already been parsed, starting with its `#[attributes]`
+pub fn parse_item(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    doc: Option<InternedStr>,
+) -> Result<Ast, ParseError> {
+    let attributes = parse_attributes(lexer, options)?;
     Ok(match lexer.next_token()? {
         Token {
             kind: TokenKind::Fn,
             location,
-        } => parse_fn(lexer, location)?,
+        } => parse_fn(lexer, options, doc, attributes, location)?,
+
+        Token {
+            kind: TokenKind::Extern,
+            location,
+        } => parse_extern_fn(lexer, options, doc, attributes, location)?,
+
+        Token {
+            kind: TokenKind::Struct,
+            location,
+        } => parse_struct(lexer, options, doc, attributes, location)?,
+
+        Token {
+            kind: TokenKind::Enum,
+            location,
+        } => parse_enum(lexer, options, doc, attributes, location)?,
 
         Token { kind, location } => {
             return Err(ParseError {
@@ -76,18 +358,26 @@ pub fn parse_global(lexer: &mut Lexer<'_>) -> Result<Ast, ParseError> {
     })
 }
 
-pub fn parse_statement(lexer: &mut Lexer<'_>) -> Result<Ast, ParseError> {
+/// Parses a statement, which ends in a `;` unless it is an item, a `for` loop or starts with a block-like expression
+///
+/// A block-like expression (`if`, `match` or a block) at the start of a statement ends the statement at its closing
+/// brace, with an optional `;` after it, so `if c { ... } -x;` is two statements. `let` and `return` always need
+/// the `;`, even when their value is block-like
+pub fn parse_statement(lexer: &mut Lexer<'_>, options: &ParserOptions) -> Result<Ast, ParseError> {
+    // doc comments before anything other than an item are ordinary comments
+    let doc = parse_doc_comments(lexer)?;
     let start_location = lexer.location();
     Ok(match lexer.peek_token()?.kind {
-        TokenKind::Fn => {
-            let fn_location = expect_token!(lexer, TokenKind::Fn)?.location;
-            parse_fn(lexer, fn_location)?
-        }
+        TokenKind::Hash
+        | TokenKind::Fn
+        | TokenKind::Extern
+        | TokenKind::Struct
+        | TokenKind::Enum => parse_item(lexer, options, doc)?,
 
         TokenKind::Let => {
-            let pattern = parse_pattern(lexer, true)?;
+            let pattern = parse_pattern(lexer, options, true)?;
             let equals = expect_token!(lexer, TokenKind::Equals)?.location;
-            let value = Box::new(parse_expression(lexer)?);
+            let value = Box::new(parse_expression(lexer, options)?);
             expect_token!(lexer, TokenKind::Semicolon)?;
             Ast {
                 kind: AstKind::Let {
@@ -99,9 +389,14 @@ pub fn parse_statement(lexer: &mut Lexer<'_>) -> Result<Ast, ParseError> {
             }
         }
 
+        TokenKind::For => {
+            expect_token!(lexer, TokenKind::For)?;
+            parse_for(lexer, options, start_location)?
+        }
+
         TokenKind::Return => {
             expect_token!(lexer, TokenKind::Return)?;
-            let expression = parse_expression(lexer)?;
+            let expression = parse_expression(lexer, options)?;
             expect_token!(lexer, TokenKind::Semicolon)?;
             Ast {
                 kind: AstKind::Return { expression },
@@ -109,51 +404,268 @@ pub fn parse_statement(lexer: &mut Lexer<'_>) -> Result<Ast, ParseError> {
             }
         }
 
-        _ => {
-            let expression = parse_expression(lexer)?;
-            expect_token!(lexer, TokenKind::Semicolon)?;
+        TokenKind::If | TokenKind::Match | TokenKind::OpenBrace => {
+            let expression = parse_primary_expression(lexer, options)?;
+            lexer.eat(&TokenKind::Semicolon)?;
             Ast {
                 kind: AstKind::Expression(expression),
                 location: start_location,
             }
         }
+
+        _ => {
+            let expression = parse_expression(lexer, options)?;
+            if let TokenKind::Equals = lexer.peek_token()?.kind {
+                if !expression.is_place_expression() {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::InvalidAssignmentTarget,
+                        location: expression.location,
+                    });
+                }
+                let equals = expect_token!(lexer, TokenKind::Equals)?.location;
+                let value = Box::new(parse_expression(lexer, options)?);
+                expect_token!(lexer, TokenKind::Semicolon)?;
+                Ast {
+                    kind: AstKind::Assignment {
+                        target: expression,
+                        equals,
+                        value,
+                    },
+                    location: start_location,
+                }
+            } else {
+                expect_token!(lexer, TokenKind::Semicolon)?;
+                Ast {
+                    kind: AstKind::Expression(expression),
+                    location: start_location,
+                }
+            }
+        }
     })
 }
 
-pub fn parse_fn(lexer: &mut Lexer<'_>, fn_location: Location) -> Result<Ast, ParseError> {
+/// Parses the `/** ... */` comments before an item, several of them are joined by newlines
+pub fn parse_doc_comments(lexer: &mut Lexer<'_>) -> Result<Option<InternedStr>, ParseError> {
+    let mut lines = vec![];
+    while let Some(doc) = lexer.next_doc_comment()? {
+        lines.push(doc.to_str());
+    }
+    Ok((!lines.is_empty()).then(|| InternedStr::intern(&lines.join("\n"))))
+}
+
+pub fn parse_attributes(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<Vec<AstAttribute>, ParseError> {
+    let mut attributes = vec![];
+    while let TokenKind::Hash = lexer.peek_token()?.kind {
+        let location = expect_token!(lexer, TokenKind::Hash)?.location;
+        expect_token!(lexer, TokenKind::OpenBracket)?;
+        let name = expect_token!(lexer, TokenKind::Name(_))?;
+        let arguments = if let TokenKind::OpenParenthesis = lexer.peek_token()?.kind {
+            expect_token!(lexer, TokenKind::OpenParenthesis)?;
+            let mut arguments = vec![];
+            while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
+                check_list_length(lexer, options, arguments.len())?;
+                arguments.push(parse_expression(lexer, options)?);
+                parse_list_separator(lexer, options, &TokenKind::CloseParenthesis)?;
+            }
+            expect_token!(lexer, TokenKind::CloseParenthesis)?;
+            Some(arguments)
+        } else {
+            None
+        };
+        expect_token!(lexer, TokenKind::CloseBracket)?;
+        attributes.push(AstAttribute {
+            name,
+            arguments,
+            location,
+        });
+    }
+    Ok(attributes)
+}
+
+pub fn parse_fn(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    doc: Option<InternedStr>,
+    attributes: Vec<AstAttribute>,
+    fn_location: Location,
+) -> Result<Ast, ParseError> {
     let name = expect_token!(lexer, TokenKind::Name(_))?;
+    let generics = parse_generic_parameters(lexer, options)?;
+    let (arguments, return_type) = parse_fn_signature(lexer, options)?;
 
-    expect_token!(lexer, TokenKind::OpenParenthesis)?;
-    let mut arguments = vec![];
-    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
-        arguments.push(parse_pattern(lexer, false)?);
-        if !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
-            expect_token!(lexer, TokenKind::Comma)?;
+    let mut where_clause = vec![];
+    if let TokenKind::Where = lexer.peek_token()?.kind {
+        expect_token!(lexer, TokenKind::Where)?;
+        loop {
+            let typ = expect_token!(lexer, TokenKind::Name(_))?;
+            expect_token!(lexer, TokenKind::Colon)?;
+            where_clause.push((typ, parse_type(lexer, options)?));
+            if let TokenKind::Comma = lexer.peek_token()?.kind {
+                expect_token!(lexer, TokenKind::Comma)?;
+                if let TokenKind::OpenBrace = lexer.peek_token()?.kind {
+                    break;
+                }
+            } else {
+                break;
+            }
         }
     }
-    expect_token!(lexer, TokenKind::CloseParenthesis)?;
-
-    let return_type = if let TokenKind::RightArrow = lexer.peek_token()?.kind {
-        expect_token!(lexer, TokenKind::RightArrow)?;
-        Some(Box::new(parse_expression(lexer)?))
-    } else {
-        None
-    };
 
-    let body = parse_block(lexer, None)?;
+    let body = parse_block(lexer, options, None)?;
 
     Ok(Ast {
         kind: AstKind::Function {
+            doc,
+            attributes,
             name,
+            generics,
             arguments,
             return_type,
+            where_clause,
             body,
         },
         location: fn_location,
     })
 }
 
-pub fn parse_primary_expression(lexer: &mut Lexer<'_>) -> Result<AstExpression, ParseError> {
+/// Parses `extern fn name(arguments) -> return_type;`
+pub fn parse_extern_fn(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    doc: Option<InternedStr>,
+    attributes: Vec<AstAttribute>,
+    location: Location,
+) -> Result<Ast, ParseError> {
+    expect_token!(lexer, TokenKind::Fn)?;
+    let name = expect_token!(lexer, TokenKind::Name(_))?;
+    let (arguments, return_type) = parse_fn_signature(lexer, options)?;
+    expect_token!(lexer, TokenKind::Semicolon)?;
+    Ok(Ast {
+        kind: AstKind::ExternFunction {
+            doc,
+            attributes,
+            name,
+            arguments,
+            return_type,
+        },
+        location,
+    })
+}
+
+/// Parses `struct Name { field: type, ... }`
+pub fn parse_struct(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    doc: Option<InternedStr>,
+    attributes: Vec<AstAttribute>,
+    location: Location,
+) -> Result<Ast, ParseError> {
+    let name = expect_token!(lexer, TokenKind::Name(_))?;
+    expect_token!(lexer, TokenKind::OpenBrace)?;
+    let mut fields = vec![];
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBrace) {
+        check_list_length(lexer, options, fields.len())?;
+        let field_name = expect_token!(lexer, TokenKind::Name(_))?;
+        expect_token!(lexer, TokenKind::Colon)?;
+        fields.push((field_name, parse_type(lexer, options)?));
+        parse_list_separator(lexer, options, &TokenKind::CloseBrace)?;
+    }
+    expect_token!(lexer, TokenKind::CloseBrace)?;
+    Ok(Ast {
+        kind: AstKind::Struct {
+            doc,
+            attributes,
+            name,
+            fields,
+        },
+        location,
+    })
+}
+
+pub fn parse_enum(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    doc: Option<InternedStr>,
+    attributes: Vec<AstAttribute>,
+    location: Location,
+) -> Result<Ast, ParseError> {
+    let name = expect_token!(lexer, TokenKind::Name(_))?;
+    expect_token!(lexer, TokenKind::OpenBrace)?;
+    let mut variants = vec![];
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBrace) {
+        check_list_length(lexer, options, variants.len())?;
+        let variant_name = expect_token!(lexer, TokenKind::Name(_))?;
+        let mut payload = vec![];
+        if lexer.eat(&TokenKind::OpenParenthesis)?.is_some() {
+            while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
+                check_list_length(lexer, options, payload.len())?;
+                payload.push(parse_type(lexer, options)?);
+                parse_list_separator(lexer, options, &TokenKind::CloseParenthesis)?;
+            }
+            expect_token!(lexer, TokenKind::CloseParenthesis)?;
+        }
+        variants.push((variant_name, payload));
+        parse_list_separator(lexer, options, &TokenKind::CloseBrace)?;
+    }
+    expect_token!(lexer, TokenKind::CloseBrace)?;
+    Ok(Ast {
+        kind: AstKind::Enum {
+            doc,
+            attributes,
+            name,
+            variants,
+        },
+        location,
+    })
+}
+
+/// Parses the `<T, ...>` type parameters after a function's name, if there are any
+pub fn parse_generic_parameters(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<Vec<Token>, ParseError> {
+    let mut generics = vec![];
+    if lexer.eat(&TokenKind::Less)?.is_some() {
+        while !matches!(lexer.peek_token()?.kind, TokenKind::Greater) {
+            check_list_length(lexer, options, generics.len())?;
+            generics.push(expect_token!(lexer, TokenKind::Name(_))?);
+            parse_list_separator(lexer, options, &TokenKind::Greater)?;
+        }
+        expect_token!(lexer, TokenKind::Greater)?;
+    }
+    Ok(generics)
+}
+
+/// Parses the `(arguments) -> return_type` shared by functions and extern functions, after the name
+pub fn parse_fn_signature(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<(Vec<AstPattern>, Option<Box<AstExpression>>), ParseError> {
+    expect_token!(lexer, TokenKind::OpenParenthesis)?;
+    let mut arguments = vec![];
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
+        check_list_length(lexer, options, arguments.len())?;
+        arguments.push(parse_pattern(lexer, options, false)?);
+        parse_list_separator(lexer, options, &TokenKind::CloseParenthesis)?;
+    }
+    expect_token!(lexer, TokenKind::CloseParenthesis)?;
+
+    let return_type = if let TokenKind::RightArrow = lexer.peek_token()?.kind {
+        expect_token!(lexer, TokenKind::RightArrow)?;
+        Some(Box::new(parse_type(lexer, options)?))
+    } else {
+        None
+    };
+    Ok((arguments, return_type))
+}
+
+pub fn parse_primary_expression(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<AstExpression, ParseError> {
     Ok(match lexer.next_token()? {
         Token {
             kind: TokenKind::Integer(value),
@@ -163,6 +675,47 @@ pub fn parse_primary_expression(lexer: &mut Lexer<'_>) -> Result<AstExpression,
             location,
         },
 
+        Token {
+            kind: TokenKind::Float(value),
+            location,
+        } => AstExpression {
+            kind: AstExpressionKind::Float(value),
+            location,
+        },
+
+        Token {
+            kind: TokenKind::String(value),
+            location,
+        } => AstExpression {
+            kind: AstExpressionKind::String(value),
+            location,
+        },
+
+        Token {
+            kind: TokenKind::Char(value),
+            location,
+        } => AstExpression {
+            kind: AstExpressionKind::Char(value),
+            location,
+        },
+
+        Token {
+            kind: kind @ (TokenKind::True | TokenKind::False),
+            location,
+        } => AstExpression {
+            kind: AstExpressionKind::Boolean(matches!(kind, TokenKind::True)),
+            location,
+        },
+
+        name @ Token {
+            kind: TokenKind::Name(_),
+            location: _,
+        } if options.struct_literals
+            && matches!(lexer.peek_token()?.kind, TokenKind::OpenBrace) =>
+        {
+            parse_struct_literal(lexer, options, name)?
+        }
+
         Token {
             kind: TokenKind::Name(name),
             location,
@@ -173,17 +726,40 @@ pub fn parse_primary_expression(lexer: &mut Lexer<'_>) -> Result<AstExpression,
 
         Token {
             kind: TokenKind::OpenParenthesis,
-            ..
+            location,
+        } if lexer.eat(&TokenKind::CloseParenthesis)?.is_some() => AstExpression {
+            kind: AstExpressionKind::Unit,
+            location,
+        },
+
+        Token {
+            kind: TokenKind::OpenParenthesis,
+            location,
         } => {
-            let expression = parse_expression(lexer)?;
-            expect_token!(lexer, TokenKind::CloseParenthesis)?;
-            expression
+            let expression = parse_expression(lexer, &delimited_options(options))?;
+            let close_parenthesis = expect_token!(lexer, TokenKind::CloseParenthesis)?.location;
+            wrap_paren(options, expression, location, close_parenthesis)
         }
 
         Token {
             kind: TokenKind::OpenBrace,
             location,
-        } => parse_block(lexer, Some(location))?,
+        } => parse_block(lexer, options, Some(location))?,
+
+        Token {
+            kind: TokenKind::OpenBracket,
+            location,
+        } => parse_array(lexer, options, location)?,
+
+        Token {
+            kind: TokenKind::If,
+            location,
+        } => parse_if(lexer, options, location)?,
+
+        Token {
+            kind: TokenKind::Match,
+            location,
+        } => parse_match(lexer, options, location)?,
 
         Token { kind, location } => {
             return Err(ParseError {
@@ -196,20 +772,20 @@ pub fn parse_primary_expression(lexer: &mut Lexer<'_>) -> Result<AstExpression,
 
 pub fn parse_binary_expression(
     lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
     parent_precedence: Option<NonZero<u8>>,
 ) -> Result<AstExpression, ParseError> {
-    let mut left = parse_primary_expression(lexer)?;
+    let mut left = parse_unary_expression(lexer, options)?;
 
     loop {
         left = if let Some(operator) = BinaryOperator::from_token_kind(lexer.peek_token()?.kind) {
             let precedence = operator.precedence();
-            if parent_precedence.map_or(false, |parent_precedence| precedence <= parent_precedence)
-            {
+            if parent_precedence.is_some_and(|parent_precedence| precedence <= parent_precedence) {
                 break;
             }
 
             let location = lexer.next_token()?.location;
-            let right = Box::new(parse_binary_expression(lexer, Some(precedence))?);
+            let right = Box::new(parse_binary_expression(lexer, options, Some(precedence))?);
             AstExpression {
                 kind: AstExpressionKind::Binary {
                     left: Box::new(left),
@@ -220,23 +796,60 @@ pub fn parse_binary_expression(
             }
         } else if let TokenKind::OpenParenthesis = lexer.peek_token()?.kind {
             let location = expect_token!(lexer, TokenKind::OpenParenthesis)?.location;
+            let argument_options = &delimited_options(options);
             let mut arguments = vec![];
+            let mut commas = vec![];
             while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
-                arguments.push(parse_expression(lexer)?);
-                if !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
-                    expect_token!(lexer, TokenKind::Comma)?;
-                }
+                check_list_length(lexer, options, arguments.len())?;
+                arguments.push(parse_expression(lexer, argument_options)?);
+                commas.extend(parse_list_separator(
+                    lexer,
+                    options,
+                    &TokenKind::CloseParenthesis,
+                )?);
             }
             let close_parenthesis = expect_token!(lexer, TokenKind::CloseParenthesis)?.location;
 
+            // a block directly after the parentheses is a trailing closure, passed as the last argument
+            if let (true, TokenKind::OpenBrace) =
+                (options.trailing_closures, lexer.peek_token()?.kind)
+            {
+                check_list_length(lexer, options, arguments.len())?;
+                let open_brace = expect_token!(lexer, TokenKind::OpenBrace)?.location;
+                arguments.push(parse_closure(lexer, options, open_brace)?);
+            }
+
             AstExpression {
                 kind: AstExpressionKind::Call {
                     operand: Box::new(left),
                     arguments,
+                    commas,
                     close_parenthesis,
                 },
                 location,
             }
+        } else if let TokenKind::Dot = lexer.peek_token()?.kind {
+            let location = expect_token!(lexer, TokenKind::Dot)?.location;
+            let name_token = expect_token!(lexer, TokenKind::Name(_))?;
+            AstExpression {
+                kind: AstExpressionKind::FieldAccess {
+                    operand: Box::new(left),
+                    name_token,
+                },
+                location,
+            }
+        } else if let TokenKind::OpenBracket = lexer.peek_token()?.kind {
+            let location = expect_token!(lexer, TokenKind::OpenBracket)?.location;
+            let index = Box::new(parse_expression(lexer, &delimited_options(options))?);
+            let close_bracket = expect_token!(lexer, TokenKind::CloseBracket)?.location;
+            AstExpression {
+                kind: AstExpressionKind::Index {
+                    operand: Box::new(left),
+                    index,
+                    close_bracket,
+                },
+                location,
+            }
         } else {
             break;
         };
@@ -245,12 +858,203 @@ pub fn parse_binary_expression(
     Ok(left)
 }
 
-pub fn parse_expression(lexer: &mut Lexer<'_>) -> Result<AstExpression, ParseError> {
-    parse_binary_expression(lexer, None)
+/// Parses any prefix operators before an operand, which bind tighter than binary operators but looser than calls,
+/// so `-a * b` is `(-a) * b` and `-f(x)` negates the result of the call
+pub fn parse_unary_expression(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<AstExpression, ParseError> {
+    let Some(operator) = UnaryOperator::from_token_kind(lexer.peek_token()?.kind) else {
+        return parse_primary_expression(lexer, options);
+    };
+    let location = lexer.next_token()?.location;
+    // no binary operator has a precedence above the maximum, so this only continues with calls
+    let operand = Box::new(parse_binary_expression(
+        lexer,
+        options,
+        Some(NonZero::<u8>::MAX),
+    )?);
+    Ok(AstExpression {
+        kind: AstExpressionKind::Unary { operator, operand },
+        location,
+    })
+}
+
+pub fn parse_expression(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<AstExpression, ParseError> {
+    parse_range_expression(lexer, options)
+}
+
+/// Parses `start..end` or `start..=end` with either bound optional,
+/// ranges bind looser than every binary operator so `0..n + 1` is `0..(n + 1)`
+pub fn parse_range_expression(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<AstExpression, ParseError> {
+    let start = if let TokenKind::DotDot | TokenKind::DotDotEquals = lexer.peek_token()?.kind {
+        None
+    } else {
+        let start = parse_binary_expression(lexer, options, None)?;
+        let (TokenKind::DotDot | TokenKind::DotDotEquals) = lexer.peek_token()?.kind else {
+            return Ok(start);
+        };
+        Some(Box::new(start))
+    };
+
+    let Token { kind, location } = lexer.next_token()?;
+    let inclusive = matches!(kind, TokenKind::DotDotEquals);
+    // a block cannot start the end in a condition, so `for i in 0.. { ... }` has no end
+    let has_end = match lexer.peek_token()?.kind {
+        TokenKind::EOF
+        | TokenKind::CloseParenthesis
+        | TokenKind::CloseBracket
+        | TokenKind::CloseBrace
+        | TokenKind::Comma
+        | TokenKind::Semicolon => false,
+        TokenKind::OpenBrace => options.trailing_closures,
+        _ => true,
+    };
+    let end = if has_end {
+        Some(Box::new(parse_binary_expression(lexer, options, None)?))
+    } else if inclusive {
+        return Err(ParseError {
+            kind: ParseErrorKind::InclusiveRangeWithoutEnd,
+            location,
+        });
+    } else {
+        None
+    };
+
+    Ok(AstExpression {
+        kind: AstExpressionKind::Range {
+            start,
+            end,
+            inclusive,
+        },
+        location,
+    })
+}
+
+/// Parses the patterns after a `(`, where a single pattern without a trailing comma is only in parentheses
+pub fn parse_tuple_pattern(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    open_parenthesis: Location,
+) -> Result<AstPattern, ParseError> {
+    let mut elements = vec![];
+    if !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
+        let first = parse_pattern(lexer, options, false)?;
+        // the comma of `(a,)` is what makes it a tuple, so it is allowed even without `trailing_commas`
+        if lexer.eat(&TokenKind::Comma)?.is_none() {
+            expect_token!(lexer, TokenKind::CloseParenthesis)?;
+            return Ok(first);
+        }
+        elements.push(first);
+    }
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
+        check_list_length(lexer, options, elements.len())?;
+        elements.push(parse_pattern(lexer, options, false)?);
+        parse_list_separator(lexer, options, &TokenKind::CloseParenthesis)?;
+    }
+    expect_token!(lexer, TokenKind::CloseParenthesis)?;
+    Ok(AstPattern {
+        kind: AstPatternKind::Tuple(elements),
+        location: open_parenthesis,
+    })
+}
+
+/// Parses the restricted subset of expressions that can denote a type: names, parentheses and applications like `f(T)`
+pub fn parse_type(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<AstExpression, ParseError> {
+    let mut typ = match lexer.next_token()? {
+        Token {
+            kind: TokenKind::Name(name),
+            location,
+        } => AstExpression {
+            kind: AstExpressionKind::Name(name),
+            location,
+        },
+
+        Token {
+            kind: TokenKind::OpenParenthesis,
+            location,
+        } if lexer.eat(&TokenKind::CloseParenthesis)?.is_some() => AstExpression {
+            kind: AstExpressionKind::Unit,
+            location,
+        },
+
+        Token {
+            kind: TokenKind::OpenParenthesis,
+            location,
+        } => {
+            let typ = parse_type(lexer, options)?;
+            let close_parenthesis = expect_token!(lexer, TokenKind::CloseParenthesis)?.location;
+            wrap_paren(options, typ, location, close_parenthesis)
+        }
+
+        Token { kind, location } => {
+            return Err(ParseError {
+                kind: ParseErrorKind::ExpectedType(kind),
+                location,
+            });
+        }
+    };
+
+    while let TokenKind::OpenParenthesis = lexer.peek_token()?.kind {
+        let location = expect_token!(lexer, TokenKind::OpenParenthesis)?.location;
+        let mut arguments = vec![];
+        let mut commas = vec![];
+        while !matches!(lexer.peek_token()?.kind, TokenKind::CloseParenthesis) {
+            check_list_length(lexer, options, arguments.len())?;
+            arguments.push(parse_type(lexer, options)?);
+            commas.extend(parse_list_separator(
+                lexer,
+                options,
+                &TokenKind::CloseParenthesis,
+            )?);
+        }
+        let close_parenthesis = expect_token!(lexer, TokenKind::CloseParenthesis)?.location;
+
+        typ = AstExpression {
+            kind: AstExpressionKind::Call {
+                operand: Box::new(typ),
+                arguments,
+                commas,
+                close_parenthesis,
+            },
+            location,
+        };
+    }
+
+    Ok(typ)
+}
+
+fn wrap_paren(
+    options: &ParserOptions,
+    inner: AstExpression,
+    open_parenthesis: Location,
+    close_parenthesis: Location,
+) -> AstExpression {
+    if options.preserve_parens {
+        AstExpression {
+            kind: AstExpressionKind::Paren {
+                inner: Box::new(inner),
+                close_parenthesis,
+            },
+            location: open_parenthesis,
+        }
+    } else {
+        inner
+    }
 }
 
 pub fn parse_block(
     lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
     open_brace_location: Option<Location>,
 ) -> Result<AstExpression, ParseError> {
     let location = if let Some(location) = open_brace_location {
@@ -260,7 +1064,7 @@ pub fn parse_block(
     };
     let mut statements = vec![];
     while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBrace) {
-        statements.push(parse_statement(lexer)?);
+        statements.push(parse_statement(lexer, options)?);
     }
     let close_brace = expect_token!(lexer, TokenKind::CloseBrace)?.location;
     Ok(AstExpression {
@@ -272,8 +1076,292 @@ pub fn parse_block(
     })
 }
 
-pub fn parse_pattern(lexer: &mut Lexer<'_>, requires_let: bool) -> Result<AstPattern, ParseError> {
+/// Parses the rest of a `for` loop after the `for` keyword
+///
+/// Struct literals and trailing closures are not parsed in the iterable, as the block after it is always the body
+pub fn parse_for(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    for_location: Location,
+) -> Result<Ast, ParseError> {
+    let pattern = parse_pattern(lexer, options, false)?;
+    expect_token!(lexer, TokenKind::In)?;
+    let iterable_options = &ParserOptions {
+        trailing_closures: false,
+        struct_literals: false,
+        ..options.clone()
+    };
+    let iterable = Box::new(parse_expression(lexer, iterable_options)?);
+    let body = Box::new(parse_block(lexer, options, None)?);
+    Ok(Ast {
+        kind: AstKind::For {
+            pattern,
+            iterable,
+            body,
+        },
+        location: for_location,
+    })
+}
+
+/// Parses the rest of `[a, b, c]` or `[value; count]` after the `[`
+pub fn parse_array(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    open_bracket: Location,
+) -> Result<AstExpression, ParseError> {
+    let options = &delimited_options(options);
+    let mut elements = vec![];
+    if !matches!(lexer.peek_token()?.kind, TokenKind::CloseBracket) {
+        let value = parse_expression(lexer, options)?;
+        if let TokenKind::Semicolon = lexer.peek_token()?.kind {
+            expect_token!(lexer, TokenKind::Semicolon)?;
+            let count = Box::new(parse_expression(lexer, options)?);
+            let close_bracket = expect_token!(lexer, TokenKind::CloseBracket)?.location;
+            return Ok(AstExpression {
+                kind: AstExpressionKind::ArrayRepeat {
+                    value: Box::new(value),
+                    count,
+                    close_bracket,
+                },
+                location: open_bracket,
+            });
+        }
+        check_list_length(lexer, options, elements.len())?;
+        elements.push(value);
+        parse_list_separator(lexer, options, &TokenKind::CloseBracket)?;
+    }
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBracket) {
+        check_list_length(lexer, options, elements.len())?;
+        elements.push(parse_expression(lexer, options)?);
+        parse_list_separator(lexer, options, &TokenKind::CloseBracket)?;
+    }
+    let close_bracket = expect_token!(lexer, TokenKind::CloseBracket)?.location;
+    Ok(AstExpression {
+        kind: AstExpressionKind::Array {
+            elements,
+            close_bracket,
+        },
+        location: open_bracket,
+    })
+}
+
+/// The options inside `(...)` and `[...]`, where a `{` can never be the block after a condition
+fn delimited_options(options: &ParserOptions) -> ParserOptions {
+    ParserOptions {
+        struct_literals: true,
+        ..options.clone()
+    }
+}
+
+/// Parses the `{ field: value, ... }` after the struct's name
+///
+/// `Name {` is only a struct literal when `ParserOptions::struct_literals` is set, which `if` conditions and
+/// `for` iterables and `match` scrutinees turn off, so `if ready { ... }` is a condition followed by a block, not a struct literal.
+/// Parentheses, call arguments and brackets turn it back on, so `if (Point { x: 0 }).x { ... }`
+/// and `if f(Point { x: 0 }) { ... }` still work
+pub fn parse_struct_literal(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    name: Token,
+) -> Result<AstExpression, ParseError> {
+    expect_token!(lexer, TokenKind::OpenBrace)?;
+    let mut fields = vec![];
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBrace) {
+        check_list_length(lexer, options, fields.len())?;
+        let field_name = expect_token!(lexer, TokenKind::Name(_))?;
+        expect_token!(lexer, TokenKind::Colon)?;
+        fields.push((field_name, parse_expression(lexer, options)?));
+        parse_list_separator(lexer, options, &TokenKind::CloseBrace)?;
+    }
+    let close_brace = expect_token!(lexer, TokenKind::CloseBrace)?.location;
+    let location = name.location;
+    Ok(AstExpression {
+        kind: AstExpressionKind::StructLiteral {
+            name,
+            fields,
+            close_brace,
+        },
+        location,
+    })
+}
+
+/// Parses the rest of an `if` after the `if` keyword, including any `else if` and `else` after it
+///
+/// Trailing closures are not parsed in the condition, as the block after it is always the body of the `if`
+pub fn parse_if(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    if_location: Location,
+) -> Result<AstExpression, ParseError> {
+    let condition_options = &ParserOptions {
+        trailing_closures: false,
+        struct_literals: false,
+        ..options.clone()
+    };
+    let condition = Box::new(parse_expression(lexer, condition_options)?);
+    let then_block = Box::new(parse_block(lexer, options, None)?);
+    let else_block = if let TokenKind::Else = lexer.peek_token()?.kind {
+        expect_token!(lexer, TokenKind::Else)?;
+        Some(Box::new(match lexer.next_token()? {
+            Token {
+                kind: TokenKind::If,
+                location,
+            } => parse_if(lexer, options, location)?,
+            Token {
+                kind: TokenKind::OpenBrace,
+                location,
+            } => parse_block(lexer, options, Some(location))?,
+            Token { kind, location } => {
+                return Err(ParseError {
+                    kind: ParseErrorKind::UnexpectedToken(kind),
+                    location,
+                });
+            }
+        }))
+    } else {
+        None
+    };
+    Ok(AstExpression {
+        kind: AstExpressionKind::If {
+            condition,
+            then_block,
+            else_block,
+        },
+        location: if_location,
+    })
+}
+
+/// Parses the rest of a closure like `{ a, b -> statements }` or `{ statements }`
+pub fn parse_closure(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    open_brace_location: Location,
+) -> Result<AstExpression, ParseError> {
+    // the argument list can only be recognised once the `->` is reached, so it is parsed speculatively
+    let arguments = {
+        let mut arguments_lexer = lexer.clone();
+        match parse_closure_arguments(&mut arguments_lexer, options) {
+            Ok(arguments) => {
+                *lexer = arguments_lexer;
+                arguments
+            }
+            Err(_) => vec![],
+        }
+    };
+
+    let mut statements = vec![];
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBrace) {
+        statements.push(parse_statement(lexer, options)?);
+    }
+    let close_brace = expect_token!(lexer, TokenKind::CloseBrace)?.location;
+
+    Ok(AstExpression {
+        kind: AstExpressionKind::Closure {
+            arguments,
+            statements,
+            close_brace,
+        },
+        location: open_brace_location,
+    })
+}
+
+fn parse_closure_arguments(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+) -> Result<Vec<AstPattern>, ParseError> {
+    let mut arguments = vec![];
+    loop {
+        check_list_length(lexer, options, arguments.len())?;
+        arguments.push(parse_pattern(lexer, options, false)?);
+        if let TokenKind::RightArrow = lexer.peek_token()?.kind {
+            break;
+        }
+        expect_token!(lexer, TokenKind::Comma)?;
+    }
+    expect_token!(lexer, TokenKind::RightArrow)?;
+    Ok(arguments)
+}
+
+pub fn parse_match(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    match_location: Location,
+) -> Result<AstExpression, ParseError> {
+    let scrutinee_options = &ParserOptions {
+        trailing_closures: false,
+        struct_literals: false,
+        ..options.clone()
+    };
+    let scrutinee = Box::new(parse_expression(lexer, scrutinee_options)?);
+    expect_token!(lexer, TokenKind::OpenBrace)?;
+    let mut arms = vec![];
+    while !matches!(lexer.peek_token()?.kind, TokenKind::CloseBrace) {
+        check_list_length(lexer, options, arms.len())?;
+        let pattern = parse_pattern(lexer, options, false)?;
+        expect_token!(lexer, TokenKind::FatArrow)?;
+        arms.push((pattern, parse_expression(lexer, options)?));
+        parse_list_separator(lexer, options, &TokenKind::CloseBrace)?;
+    }
+    let close_brace = expect_token!(lexer, TokenKind::CloseBrace)?.location;
+    Ok(AstExpression {
+        kind: AstExpressionKind::Match {
+            scrutinee,
+            arms,
+            close_brace,
+        },
+        location: match_location,
+    })
+}
+
+/// Rejects the elements of a tuple after a `let` that a `let` name could not have been used for
+fn check_let_pattern(pattern: &AstPattern) -> Result<(), ParseError> {
+    match pattern.kind {
+        AstPatternKind::Let { .. } | AstPatternKind::Wildcard => Ok(()),
+        AstPatternKind::Tuple(ref elements) => elements.iter().try_for_each(check_let_pattern),
+        AstPatternKind::Binding { .. } => Err(ParseError {
+            kind: ParseErrorKind::BindingInLet,
+            location: pattern.location,
+        }),
+        AstPatternKind::Integer(_) | AstPatternKind::Range { .. } | AstPatternKind::Boolean(_) => {
+            Err(ParseError {
+                kind: ParseErrorKind::RefutablePatternInLet,
+                location: pattern.location,
+            })
+        }
+    }
+}
+
+/// Parses a pattern, where `name @ pattern` is rejected after a `let`, as that already names the whole value,
+/// and only `_` or a tuple of names, `_` and tuples can take the place of the name there
+pub fn parse_pattern(
+    lexer: &mut Lexer<'_>,
+    options: &ParserOptions,
+    requires_let: bool,
+) -> Result<AstPattern, ParseError> {
     Ok(match lexer.next_token()? {
+        Token {
+            kind: TokenKind::Let,
+            location,
+        } if lexer.eat(&TokenKind::Underscore)?.is_some() => AstPattern {
+            kind: AstPatternKind::Wildcard,
+            location,
+        },
+
+        Token {
+            kind: TokenKind::Let,
+            location: _,
+        } if matches!(lexer.peek_token()?.kind, TokenKind::OpenParenthesis) => {
+            let open_parenthesis = expect_token!(lexer, TokenKind::OpenParenthesis)?.location;
+            let pattern = parse_tuple_pattern(lexer, options, open_parenthesis)?;
+            check_let_pattern(&pattern)?;
+            pattern
+        }
+
+        Token {
+            kind: TokenKind::OpenParenthesis,
+            location,
+        } if !requires_let => parse_tuple_pattern(lexer, options, location)?,
+
         Token {
             kind: TokenKind::Let,
             location,
@@ -283,13 +1371,73 @@ pub fn parse_pattern(lexer: &mut Lexer<'_>, requires_let: bool) -> Result<AstPat
                 name_token: expect_token!(lexer, TokenKind::Name(_))?,
                 typ: if let TokenKind::Colon = lexer.peek_token()?.kind {
                     lexer.next_token()?;
-                    Some(parse_expression(lexer)?)
+                    Some(parse_type(lexer, options)?)
+                } else if let Some(at) = lexer.eat(&TokenKind::At)? {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::BindingInLet,
+                        location: at.location,
+                    });
                 } else {
                     None
                 },
             },
         },
 
+        name @ Token {
+            kind: TokenKind::Name(_),
+            location,
+        } if !requires_let && lexer.eat(&TokenKind::At)?.is_some() => AstPattern {
+            location,
+            kind: AstPatternKind::Binding {
+                name,
+                pattern: Box::new(parse_pattern(lexer, options, false)?),
+            },
+        },
+
+        Token {
+            kind: TokenKind::Underscore,
+            location,
+        } if !requires_let => AstPattern {
+            kind: AstPatternKind::Wildcard,
+            location,
+        },
+
+        Token {
+            kind: TokenKind::Integer(start),
+            location,
+        } if !requires_let => {
+            let inclusive = match lexer.peek_token()?.kind {
+                TokenKind::DotDot => false,
+                TokenKind::DotDotEquals => true,
+                _ => {
+                    return Ok(AstPattern {
+                        kind: AstPatternKind::Integer(start),
+                        location,
+                    });
+                }
+            };
+            lexer.next_token()?;
+            let TokenKind::Integer(end) = expect_token!(lexer, TokenKind::Integer(_))?.kind else {
+                unreachable!();
+            };
+            AstPattern {
+                kind: AstPatternKind::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                location,
+            }
+        }
+
+        Token {
+            kind: kind @ (TokenKind::True | TokenKind::False),
+            location,
+        } if !requires_let => AstPattern {
+            kind: AstPatternKind::Boolean(matches!(kind, TokenKind::True)),
+            location,
+        },
+
         name_token @ Token {
             kind: TokenKind::Name(_),
             location,
@@ -299,7 +1447,7 @@ pub fn parse_pattern(lexer: &mut Lexer<'_>, requires_let: bool) -> Result<AstPat
                 name_token,
                 typ: if let TokenKind::Colon = lexer.peek_token()?.kind {
                     lexer.next_token()?;
-                    Some(parse_expression(lexer)?)
+                    Some(parse_type(lexer, options)?)
                 } else {
                     None
                 },
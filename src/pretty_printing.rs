@@ -1,109 +1,1084 @@
 use crate::{
-    ast::{Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind},
-    lexer::TokenKind,
+    ast::{
+        Ast, AstAttribute, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind,
+    },
+    interning::InternedStr,
+    lexer::{is_keyword, CharLiteral, StringLiteral, TokenKind},
+    visitor::Visitor,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{Error, Result, Write},
 };
-use std::io::{Result, Write};
 
-pub fn pretty_print_ast(
-    ast: &Ast,
+pub struct PrettyPrinter<'writer, W: Write + ?Sized> {
+    writer: ColumnWriter<'writer, W>,
     indent: usize,
-    writer: &mut (impl Write + ?Sized),
-) -> Result<()> {
-    print_indent(indent, writer)?;
-    match ast.kind {
-        AstKind::Expression(ref expression) => {
-            pretty_print_ast_expression(expression, indent, writer)?;
-            writeln!(writer, ";")?;
-        }
-        AstKind::Let {
-            ref pattern,
-            ref value,
-            ..
-        } => {
-            write!(writer, "let ")?;
-            pretty_print_ast_pattern(pattern, indent, writer)?;
-            write!(writer, " = ")?;
-            pretty_print_ast_expression(value, indent, writer)?;
-            writeln!(writer, ";")?;
+    max_width: Option<usize>,
+    integer_format: IntegerFormat,
+}
+
+/// The base that integer literals are printed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntegerBase {
+    #[default]
+    Decimal,
+    Hexadecimal,
+    Octal,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct IntegerFormat {
+    base: IntegerBase,
+    group_digits: bool,
+}
+
+impl<'writer, W: Write + ?Sized> PrettyPrinter<'writer, W> {
+    pub fn new(writer: &'writer mut W, indent: usize) -> Self {
+        Self {
+            writer: ColumnWriter { writer, column: 0 },
+            indent,
+            max_width: None,
+            integer_format: IntegerFormat::default(),
+        }
+    }
+
+    pub fn with_integer_base(mut self, base: IntegerBase) -> Self {
+        self.integer_format.base = base;
+        self
+    }
+
+    /// Separates hexadecimal and binary integer literals into bytes with `_`, like `0xFF_FF`
+    pub fn with_group_digits(mut self, group_digits: bool) -> Self {
+        self.integer_format.group_digits = group_digits;
+        self
+    }
+
+    /// Argument lists that would go past `max_width` columns are broken up with one argument per line
+    pub fn with_max_width(self, max_width: usize) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
         }
-        AstKind::Function {
-            ref name,
-            ref arguments,
-            ref return_type,
-            ref body,
-        } => {
-            write!(writer, "fn {}(", name.kind)?;
-            for (i, argument) in arguments.iter().enumerate() {
-                if i > 0 {
-                    write!(writer, ", ")?;
+    }
+
+    fn print_indent(&mut self) -> Result<()> {
+        for _ in 0..self.indent {
+            write!(self.writer, "    ")?;
+        }
+        Ok(())
+    }
+
+    /// Ranges bind looser than every operator, so they need parentheses when used as an operand
+    fn visit_operand(&mut self, expression: &AstExpression) -> Result<()> {
+        if let AstExpressionKind::Range { .. } = expression.kind {
+            write!(self.writer, "(")?;
+            self.visit_expression(expression)?;
+            write!(self.writer, ")")
+        } else {
+            self.visit_expression(expression)
+        }
+    }
+
+    /// A struct literal would be read as the name before a block in a condition, so the condition gets parentheses
+    fn visit_condition(&mut self, expression: &AstExpression) -> Result<()> {
+        if has_exterior_struct_literal(expression) {
+            write!(self.writer, "(")?;
+            self.visit_expression(expression)?;
+            write!(self.writer, ")")
+        } else {
+            self.visit_expression(expression)
+        }
+    }
+
+    /// Prints the doc comment and attributes of an item, each followed by a line break and the indentation
+    fn print_doc_and_attributes(
+        &mut self,
+        doc: Option<InternedStr>,
+        attributes: &[AstAttribute],
+    ) -> Result<()> {
+        if let Some(doc) = doc {
+            let mut lines = doc.to_str().lines();
+            if let (Some(line), None) = (lines.next(), lines.next()) {
+                writeln!(self.writer, "/** {line} */")?;
+            } else {
+                writeln!(self.writer, "/**")?;
+                for line in doc.to_str().lines() {
+                    self.print_indent()?;
+                    if line.is_empty() {
+                        writeln!(self.writer, " *")?;
+                    } else {
+                        writeln!(self.writer, " * {line}")?;
+                    }
                 }
-                pretty_print_ast_pattern(argument, indent, writer)?;
+                self.print_indent()?;
+                writeln!(self.writer, " */")?;
             }
-            write!(writer, ")")?;
-            if let Some(return_type) = return_type {
-                write!(writer, " -> ")?;
-                pretty_print_ast_expression(return_type, indent, writer)?;
+            self.print_indent()?;
+        }
+        for attribute in attributes {
+            write!(self.writer, "#[{}", attribute.name.kind)?;
+            if let Some(arguments) = &attribute.arguments {
+                write!(self.writer, "(")?;
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.writer, ", ")?;
+                    }
+                    self.visit_expression(argument)?;
+                }
+                write!(self.writer, ")")?;
             }
-            write!(writer, " ")?;
-            pretty_print_ast_expression(body, indent, writer)?;
-            writeln!(writer)?;
+            writeln!(self.writer, "]")?;
+            self.print_indent()?;
         }
-        AstKind::Return { ref expression } => {
-            write!(writer, "return ")?;
-            pretty_print_ast_expression(expression, indent, writer)?;
-            writeln!(writer, ";")?;
+        Ok(())
+    }
+
+    fn print_arguments<A: Argument>(&mut self, arguments: &[A]) -> Result<()> {
+        let wrap = self.max_width.is_some_and(|max_width| {
+            let mut width = LineWidth {
+                integer_format: self.integer_format,
+                ..LineWidth::default()
+            };
+            width.arguments(arguments);
+            self.writer.column + width.width > max_width
+        });
+
+        if wrap {
+            writeln!(self.writer, "(")?;
+            self.indent += 1;
+            for argument in arguments {
+                self.print_indent()?;
+                argument.print(self)?;
+                writeln!(self.writer, ",")?;
+            }
+            self.indent -= 1;
+            self.print_indent()?;
+            write!(self.writer, ")")
+        } else {
+            self.print_inline_arguments(arguments)
         }
     }
-    Ok(())
+
+    fn print_inline_arguments<A: Argument>(&mut self, arguments: &[A]) -> Result<()> {
+        write!(self.writer, "(")?;
+        for (i, argument) in arguments.iter().enumerate() {
+            if i > 0 {
+                write!(self.writer, ", ")?;
+            }
+            argument.print(self)?;
+        }
+        write!(self.writer, ")")
+    }
 }
 
-pub fn pretty_print_ast_expression(
-    expression: &AstExpression,
-    indent: usize,
-    writer: &mut (impl Write + ?Sized),
-) -> Result<()> {
-    match expression.kind {
-        AstExpressionKind::Name(name) => write!(writer, "{name}")?,
-        AstExpressionKind::Integer(value) => write!(writer, "{value}")?,
-        AstExpressionKind::Binary {
-            ref left,
-            ref operator,
-            ref right,
-        } => {
-            write!(writer, "(")?;
-            pretty_print_ast_expression(left, indent, writer)?;
-            write!(writer, " {operator} ")?;
-            pretty_print_ast_expression(right, indent, writer)?;
-            write!(writer, ")")?;
+/// Keeps track of the column that the next character will be written at
+struct ColumnWriter<'writer, W: Write + ?Sized> {
+    writer: &'writer mut W,
+    column: usize,
+}
+
+impl<W: Write + ?Sized> Write for ColumnWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let written = self.writer.write(buf)?;
+        let buf = &buf[..written];
+        match buf.iter().rposition(|&b| b == b'\n') {
+            Some(newline) => self.column = count_chars(&buf[newline + 1..]),
+            None => self.column += count_chars(buf),
         }
-        AstExpressionKind::Block {
-            ref statements,
-            close_brace: _,
-        } => {
-            writeln!(writer, "{{")?;
-            for statement in statements {
-                pretty_print_ast(statement, indent + 1, writer)?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn count_chars(bytes: &[u8]) -> usize {
+    // every utf-8 character has exactly one byte that is not a continuation byte
+    bytes.iter().filter(|&&b| b & 0xC0 != 0x80).count()
+}
+
+trait Argument {
+    fn print<W: Write + ?Sized>(&self, printer: &mut PrettyPrinter<'_, W>) -> Result<()>;
+    fn measure(&self, width: &mut LineWidth);
+}
+
+impl Argument for AstExpression {
+    fn print<W: Write + ?Sized>(&self, printer: &mut PrettyPrinter<'_, W>) -> Result<()> {
+        printer.visit_expression(self)
+    }
+
+    fn measure(&self, width: &mut LineWidth) {
+        width.expression(self);
+    }
+}
+
+impl Argument for AstPattern {
+    fn print<W: Write + ?Sized>(&self, printer: &mut PrettyPrinter<'_, W>) -> Result<()> {
+        printer.visit_pattern(self)
+    }
+
+    fn measure(&self, width: &mut LineWidth) {
+        width.pattern(self);
+    }
+}
+
+struct IntegerLiteral {
+    value: u64,
+    format: IntegerFormat,
+}
+
+impl Display for IntegerLiteral {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let value = self.value;
+        let (prefix, digits, group) = match self.format.base {
+            IntegerBase::Decimal => ("", value.to_string(), None),
+            IntegerBase::Hexadecimal => ("0x", format!("{value:X}"), Some(2)),
+            IntegerBase::Octal => ("0o", format!("{value:o}"), None),
+            IntegerBase::Binary => ("0b", format!("{value:b}"), Some(8)),
+        };
+        write!(f, "{prefix}")?;
+        match group.filter(|_| self.format.group_digits) {
+            Some(group) => {
+                // groups are counted from the least significant digit, so only the first one can be short
+                for (i, digit) in digits.chars().enumerate() {
+                    if i > 0 && (digits.len() - i) % group == 0 {
+                        write!(f, "_")?;
+                    }
+                    write!(f, "{digit}")?;
+                }
+                Ok(())
+            }
+            None => write!(f, "{digits}"),
+        }
+    }
+}
+
+/// Prints a float so that it lexes back as one, with a fractional part and without an exponent
+///
+/// Infinities and NaN have no literal, so they are printed as the division that produces them
+struct FloatLiteral(f64);
+
+impl Display for FloatLiteral {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_nan() {
+            return write!(f, "(0.0 / 0.0)");
+        } else if self.0.is_infinite() {
+            let sign = if self.0.is_sign_negative() { "-" } else { "" };
+            return write!(f, "({sign}1.0 / 0.0)");
+        }
+        // `Display` for floats writes every digit instead of using an exponent, so `1e20` is `100000000000000000000`
+        let literal = self.0.to_string();
+        if literal.contains('.') {
+            write!(f, "{literal}")
+        } else {
+            write!(f, "{literal}.0")
+        }
+    }
+}
+
+/// The width of `expression` when printed on one line, or up to the first line break for blocks and closures
+pub fn measure_width(expression: &AstExpression) -> usize {
+    let mut width = LineWidth::default();
+    width.expression(expression);
+    width.width
+}
+
+/// Adds up the width of the first line that the printer would output, without formatting anything
+#[derive(Default)]
+struct LineWidth {
+    width: usize,
+    broken: bool,
+    integer_format: IntegerFormat,
+}
+
+impl LineWidth {
+    fn text(&mut self, width: usize) {
+        if !self.broken {
+            self.width += width;
+        }
+    }
+
+    fn name(&mut self, name: InternedStr) {
+        let name = name.to_str();
+        self.text(if is_keyword(name) { "r#".len() } else { 0 } + name.chars().count());
+    }
+
+    fn arguments<A: Argument>(&mut self, arguments: &[A]) {
+        self.text("(".len());
+        for (i, argument) in arguments.iter().enumerate() {
+            if i > 0 {
+                self.text(", ".len());
             }
-            print_indent(indent, writer)?;
-            write!(writer, "}}")?;
+            argument.measure(self);
         }
-        AstExpressionKind::Call {
-            ref operand,
-            ref arguments,
-            close_parenthesis: _,
-        } => {
-            pretty_print_ast_expression(operand, indent, writer)?;
-            write!(writer, "(")?;
-            for (i, argument) in arguments.iter().enumerate() {
-                if i > 0 {
-                    write!(writer, ", ")?;
+        self.text(")".len());
+    }
+
+    /// Mirrors `PrettyPrinter::visit_operand`
+    fn operand(&mut self, expression: &AstExpression) {
+        if let AstExpressionKind::Range { .. } = expression.kind {
+            self.text("(".len());
+            self.expression(expression);
+            self.text(")".len());
+        } else {
+            self.expression(expression);
+        }
+    }
+
+    fn condition(&mut self, expression: &AstExpression) {
+        if has_exterior_struct_literal(expression) {
+            self.text("()".len());
+        }
+        self.expression(expression);
+    }
+
+    fn integer(&mut self, value: u64) {
+        self.text(match self.integer_format.base {
+            IntegerBase::Decimal => value
+                .checked_ilog10()
+                .map_or(1, |digits| digits as usize + 1),
+            _ => IntegerLiteral {
+                value,
+                format: self.integer_format,
+            }
+            .to_string()
+            .len(),
+        });
+    }
+
+    fn expression(&mut self, expression: &AstExpression) {
+        if self.broken {
+            return;
+        }
+        match expression.kind {
+            AstExpressionKind::Name(name) => self.name(name),
+            AstExpressionKind::Integer(value) => self.integer(value),
+            AstExpressionKind::Float(value) => self.text(FloatLiteral(value).to_string().len()),
+            AstExpressionKind::String(value) => {
+                self.text(StringLiteral(value.to_str()).to_string().chars().count());
+            }
+            AstExpressionKind::Char(value) => {
+                self.text(CharLiteral(value).to_string().chars().count());
+            }
+            AstExpressionKind::Boolean(value) => self.text(value.to_string().len()),
+            AstExpressionKind::Unit => self.text("()".len()),
+            AstExpressionKind::Unary {
+                ref operator,
+                ref operand,
+            } => {
+                self.text("(".len() + operator.to_string().len());
+                self.operand(operand);
+                self.text(")".len());
+            }
+            AstExpressionKind::Binary { .. } => {
+                // mirrors the printer's loop, as left associative chains can be arbitrarily deep
+                let mut spine = vec![];
+                let mut leftmost = expression;
+                while let AstExpressionKind::Binary {
+                    ref left,
+                    ref operator,
+                    ref right,
+                } = leftmost.kind
+                {
+                    spine.push((operator, right));
+                    leftmost = left;
+                }
+
+                self.text(spine.len() * "(".len());
+                self.operand(leftmost);
+                for (operator, right) in spine.into_iter().rev() {
+                    self.text(operator.to_string().len() + "  ".len());
+                    self.operand(right);
+                    self.text(")".len());
+                }
+            }
+            AstExpressionKind::Range {
+                ref start,
+                ref end,
+                inclusive,
+            } => {
+                if let Some(start) = start {
+                    self.operand(start);
+                }
+                self.text(if inclusive { "..=".len() } else { "..".len() });
+                if let Some(end) = end {
+                    self.operand(end);
                 }
-                pretty_print_ast_expression(argument, indent, writer)?;
             }
-            write!(writer, ")")?;
+            AstExpressionKind::Paren { ref inner, .. } => {
+                if let AstExpressionKind::Unary { .. } | AstExpressionKind::Binary { .. } =
+                    inner.kind
+                {
+                    self.expression(inner);
+                } else {
+                    self.text("(".len());
+                    self.expression(inner);
+                    self.text(")".len());
+                }
+            }
+            AstExpressionKind::Array { ref elements, .. } => {
+                self.text("[".len());
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.text(", ".len());
+                    }
+                    self.expression(element);
+                }
+                self.text("]".len());
+            }
+            AstExpressionKind::ArrayRepeat {
+                ref value,
+                ref count,
+                ..
+            } => {
+                self.text("[".len());
+                self.expression(value);
+                self.text("; ".len());
+                self.expression(count);
+                self.text("]".len());
+            }
+            AstExpressionKind::Block { .. } => {
+                self.text("{".len());
+                self.broken = true;
+            }
+            AstExpressionKind::If { ref condition, .. } => {
+                self.text("if ".len());
+                self.condition(condition);
+                self.text(" {".len());
+                self.broken = true;
+            }
+            AstExpressionKind::Match { ref scrutinee, .. } => {
+                self.text("match ".len());
+                self.condition(scrutinee);
+                self.text(" {".len());
+                self.broken = true;
+            }
+            AstExpressionKind::Call {
+                ref operand,
+                ref arguments,
+                ..
+            } => {
+                let (arguments, trailing_closure) = match arguments.split_last() {
+                    Some((
+                        closure @ AstExpression {
+                            kind: AstExpressionKind::Closure { .. },
+                            ..
+                        },
+                        arguments,
+                    )) => (arguments, Some(closure)),
+                    _ => (&arguments[..], None),
+                };
+                self.operand(operand);
+                self.arguments(arguments);
+                if let Some(closure) = trailing_closure {
+                    self.text(" ".len());
+                    self.expression(closure);
+                }
+            }
+            AstExpressionKind::StructLiteral {
+                ref name,
+                ref fields,
+                ..
+            } => {
+                let TokenKind::Name(name) = name.kind else {
+                    unreachable!();
+                };
+                self.name(name);
+                self.text(" {".len());
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    let TokenKind::Name(field) = field.kind else {
+                        unreachable!();
+                    };
+                    self.text(if i > 0 { ", ".len() } else { " ".len() });
+                    self.name(field);
+                    self.text(": ".len());
+                    self.expression(value);
+                }
+                self.text(if fields.is_empty() {
+                    "}".len()
+                } else {
+                    " }".len()
+                });
+            }
+            AstExpressionKind::FieldAccess {
+                ref operand,
+                ref name_token,
+            } => {
+                let TokenKind::Name(name) = name_token.kind else {
+                    unreachable!();
+                };
+                self.operand(operand);
+                self.text(".".len());
+                self.name(name);
+            }
+            AstExpressionKind::Index {
+                ref operand,
+                ref index,
+                ..
+            } => {
+                self.operand(operand);
+                self.text("[".len());
+                self.expression(index);
+                self.text("]".len());
+            }
+            AstExpressionKind::Closure { ref arguments, .. } => {
+                self.text("{".len());
+                for (i, argument) in arguments.iter().enumerate() {
+                    self.text(if i > 0 { ", ".len() } else { " ".len() });
+                    self.pattern(argument);
+                }
+                if !arguments.is_empty() {
+                    self.text(" ->".len());
+                }
+                self.broken = true;
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &AstPattern) {
+        match pattern.kind {
+            AstPatternKind::Let {
+                ref name_token,
+                ref typ,
+            } => {
+                let TokenKind::Name(name) = name_token.kind else {
+                    unreachable!();
+                };
+                self.name(name);
+                if let Some(typ) = typ {
+                    self.text(": ".len());
+                    self.expression(typ);
+                }
+            }
+            AstPatternKind::Wildcard => self.text("_".len()),
+            AstPatternKind::Integer(value) => self.integer(value),
+            AstPatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                self.integer(start);
+                self.text(if inclusive { "..=".len() } else { "..".len() });
+                self.integer(end);
+            }
+            AstPatternKind::Boolean(value) => self.text(value.to_string().len()),
+            AstPatternKind::Tuple(ref elements) => {
+                self.text("(".len());
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.text(", ".len());
+                    }
+                    self.pattern(element);
+                }
+                self.text(if elements.len() == 1 {
+                    ",)".len()
+                } else {
+                    ")".len()
+                });
+            }
+            AstPatternKind::Binding {
+                ref name,
+                ref pattern,
+            } => {
+                let TokenKind::Name(name) = name.kind else {
+                    unreachable!();
+                };
+                self.name(name);
+                self.text(" @ ".len());
+                self.pattern(pattern);
+            }
+        }
+    }
+}
+
+impl<W: Write + ?Sized> Visitor for PrettyPrinter<'_, W> {
+    type Error = Error;
+
+    fn visit_ast(&mut self, ast: &Ast) -> Result<()> {
+        self.print_indent()?;
+        match ast.kind {
+            AstKind::Expression(ref expression) => {
+                if expression.is_block_like() {
+                    self.visit_expression(expression)?;
+                    writeln!(self.writer)?;
+                } else if starts_with_block_like(expression) {
+                    // otherwise the statement would end at the block's closing brace
+                    write!(self.writer, "(")?;
+                    self.visit_expression(expression)?;
+                    writeln!(self.writer, ");")?;
+                } else {
+                    self.visit_expression(expression)?;
+                    writeln!(self.writer, ";")?;
+                }
+            }
+            AstKind::Let {
+                ref pattern,
+                ref value,
+                ..
+            } => {
+                write!(self.writer, "let ")?;
+                self.visit_pattern(pattern)?;
+                write!(self.writer, " = ")?;
+                self.visit_expression(value)?;
+                writeln!(self.writer, ";")?;
+            }
+            AstKind::Function {
+                doc,
+                ref attributes,
+                ref name,
+                ref generics,
+                ref arguments,
+                ref return_type,
+                ref where_clause,
+                ref body,
+            } => {
+                self.print_doc_and_attributes(doc, attributes)?;
+                write!(self.writer, "fn {}", name.kind)?;
+                if !generics.is_empty() {
+                    write!(self.writer, "<")?;
+                    for (i, generic) in generics.iter().enumerate() {
+                        if i > 0 {
+                            write!(self.writer, ", ")?;
+                        }
+                        write!(self.writer, "{}", generic.kind)?;
+                    }
+                    write!(self.writer, ">")?;
+                }
+                self.print_arguments(arguments)?;
+                if let Some(return_type) = return_type {
+                    write!(self.writer, " -> ")?;
+                    self.visit_expression(return_type)?;
+                }
+                for (i, (typ, bound)) in where_clause.iter().enumerate() {
+                    write!(
+                        self.writer,
+                        "{}{}: ",
+                        if i == 0 { " where " } else { ", " },
+                        typ.kind
+                    )?;
+                    self.visit_expression(bound)?;
+                }
+                write!(self.writer, " ")?;
+                self.visit_expression(body)?;
+                writeln!(self.writer)?;
+            }
+            AstKind::ExternFunction {
+                doc,
+                ref attributes,
+                ref name,
+                ref arguments,
+                ref return_type,
+            } => {
+                self.print_doc_and_attributes(doc, attributes)?;
+                write!(self.writer, "extern fn {}", name.kind)?;
+                self.print_arguments(arguments)?;
+                if let Some(return_type) = return_type {
+                    write!(self.writer, " -> ")?;
+                    self.visit_expression(return_type)?;
+                }
+                writeln!(self.writer, ";")?;
+            }
+            AstKind::Struct {
+                doc,
+                ref attributes,
+                ref name,
+                ref fields,
+            } => {
+                self.print_doc_and_attributes(doc, attributes)?;
+                writeln!(self.writer, "struct {} {{", name.kind)?;
+                self.indent += 1;
+                for (name, typ) in fields {
+                    self.print_indent()?;
+                    write!(self.writer, "{}: ", name.kind)?;
+                    self.visit_expression(typ)?;
+                    writeln!(self.writer, ",")?;
+                }
+                self.indent -= 1;
+                self.print_indent()?;
+                writeln!(self.writer, "}}")?;
+            }
+            AstKind::Enum {
+                doc,
+                ref attributes,
+                ref name,
+                ref variants,
+            } => {
+                self.print_doc_and_attributes(doc, attributes)?;
+                writeln!(self.writer, "enum {} {{", name.kind)?;
+                self.indent += 1;
+                for (name, payload) in variants {
+                    self.print_indent()?;
+                    write!(self.writer, "{}", name.kind)?;
+                    if !payload.is_empty() {
+                        write!(self.writer, "(")?;
+                        for (i, typ) in payload.iter().enumerate() {
+                            if i > 0 {
+                                write!(self.writer, ", ")?;
+                            }
+                            self.visit_expression(typ)?;
+                        }
+                        write!(self.writer, ")")?;
+                    }
+                    writeln!(self.writer, ",")?;
+                }
+                self.indent -= 1;
+                self.print_indent()?;
+                writeln!(self.writer, "}}")?;
+            }
+            AstKind::For {
+                ref pattern,
+                ref iterable,
+                ref body,
+            } => {
+                write!(self.writer, "for ")?;
+                self.visit_pattern(pattern)?;
+                write!(self.writer, " in ")?;
+                self.visit_condition(iterable)?;
+                write!(self.writer, " ")?;
+                self.visit_expression(body)?;
+                writeln!(self.writer)?;
+            }
+            AstKind::Return { ref expression } => {
+                write!(self.writer, "return ")?;
+                self.visit_expression(expression)?;
+                writeln!(self.writer, ";")?;
+            }
+            AstKind::Assignment {
+                ref target,
+                ref value,
+                ..
+            } => {
+                self.visit_expression(target)?;
+                write!(self.writer, " = ")?;
+                self.visit_expression(value)?;
+                writeln!(self.writer, ";")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_expression(&mut self, expression: &AstExpression) -> Result<()> {
+        match expression.kind {
+            AstExpressionKind::Name(name) => write!(self.writer, "{}", TokenKind::Name(name))?,
+            AstExpressionKind::Integer(value) => write!(
+                self.writer,
+                "{}",
+                IntegerLiteral {
+                    value,
+                    format: self.integer_format,
+                }
+            )?,
+            AstExpressionKind::Float(value) => write!(self.writer, "{}", FloatLiteral(value))?,
+            AstExpressionKind::String(value) => {
+                write!(self.writer, "{}", StringLiteral(value.to_str()))?;
+            }
+            AstExpressionKind::Char(value) => write!(self.writer, "{}", CharLiteral(value))?,
+            AstExpressionKind::Boolean(value) => write!(self.writer, "{value}")?,
+            AstExpressionKind::Unit => write!(self.writer, "()")?,
+            AstExpressionKind::Unary {
+                ref operator,
+                ref operand,
+            } => {
+                write!(self.writer, "({operator}")?;
+                self.visit_operand(operand)?;
+                write!(self.writer, ")")?;
+            }
+            AstExpressionKind::Binary { .. } => {
+                // left associative chains like `a + b + c + ...` can be arbitrarily deep,
+                // so the left operands are walked with a loop instead of recursion
+                let mut spine = vec![];
+                let mut leftmost = expression;
+                while let AstExpressionKind::Binary {
+                    ref left,
+                    ref operator,
+                    ref right,
+                } = leftmost.kind
+                {
+                    spine.push((operator, right));
+                    leftmost = left;
+                }
+
+                for _ in 0..spine.len() {
+                    write!(self.writer, "(")?;
+                }
+                self.visit_operand(leftmost)?;
+                for (operator, right) in spine.into_iter().rev() {
+                    write!(self.writer, " {operator} ")?;
+                    self.visit_operand(right)?;
+                    write!(self.writer, ")")?;
+                }
+            }
+            AstExpressionKind::Range {
+                ref start,
+                ref end,
+                inclusive,
+            } => {
+                if let Some(start) = start {
+                    self.visit_operand(start)?;
+                }
+                write!(self.writer, "{}", if inclusive { "..=" } else { ".." })?;
+                if let Some(end) = end {
+                    self.visit_operand(end)?;
+                }
+            }
+            AstExpressionKind::Paren {
+                ref inner,
+                close_parenthesis: _,
+            } => {
+                // operators already print their own parentheses
+                if let AstExpressionKind::Unary { .. } | AstExpressionKind::Binary { .. } =
+                    inner.kind
+                {
+                    self.visit_expression(inner)?;
+                } else {
+                    write!(self.writer, "(")?;
+                    self.visit_expression(inner)?;
+                    write!(self.writer, ")")?;
+                }
+            }
+            AstExpressionKind::Block {
+                ref statements,
+                close_brace: _,
+            } => {
+                writeln!(self.writer, "{{")?;
+                self.indent += 1;
+                for statement in statements {
+                    self.visit_ast(statement)?;
+                }
+                self.indent -= 1;
+                self.print_indent()?;
+                write!(self.writer, "}}")?;
+            }
+            AstExpressionKind::Array {
+                ref elements,
+                close_bracket: _,
+            } => {
+                write!(self.writer, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.writer, ", ")?;
+                    }
+                    self.visit_expression(element)?;
+                }
+                write!(self.writer, "]")?;
+            }
+            AstExpressionKind::ArrayRepeat {
+                ref value,
+                ref count,
+                close_bracket: _,
+            } => {
+                write!(self.writer, "[")?;
+                self.visit_expression(value)?;
+                write!(self.writer, "; ")?;
+                self.visit_expression(count)?;
+                write!(self.writer, "]")?;
+            }
+            AstExpressionKind::If {
+                ref condition,
+                ref then_block,
+                ref else_block,
+            } => {
+                write!(self.writer, "if ")?;
+                self.visit_condition(condition)?;
+                write!(self.writer, " ")?;
+                self.visit_expression(then_block)?;
+                if let Some(else_block) = else_block {
+                    write!(self.writer, " else ")?;
+                    self.visit_expression(else_block)?;
+                }
+            }
+            AstExpressionKind::Match {
+                ref scrutinee,
+                ref arms,
+                close_brace: _,
+            } => {
+                write!(self.writer, "match ")?;
+                self.visit_condition(scrutinee)?;
+                writeln!(self.writer, " {{")?;
+                self.indent += 1;
+                for (pattern, value) in arms {
+                    self.print_indent()?;
+                    self.visit_pattern(pattern)?;
+                    write!(self.writer, " => ")?;
+                    self.visit_expression(value)?;
+                    writeln!(self.writer, ",")?;
+                }
+                self.indent -= 1;
+                self.print_indent()?;
+                write!(self.writer, "}}")?;
+            }
+            AstExpressionKind::Call {
+                ref operand,
+                ref arguments,
+                commas: _,
+                close_parenthesis: _,
+            } => {
+                // closures can only be written in trailing position, so they are printed back that way
+                let (arguments, trailing_closure) = match arguments.split_last() {
+                    Some((
+                        closure @ AstExpression {
+                            kind: AstExpressionKind::Closure { .. },
+                            ..
+                        },
+                        arguments,
+                    )) => (arguments, Some(closure)),
+                    _ => (&arguments[..], None),
+                };
+
+                self.visit_operand(operand)?;
+                self.print_arguments(arguments)?;
+                if let Some(closure) = trailing_closure {
+                    write!(self.writer, " ")?;
+                    self.visit_expression(closure)?;
+                }
+            }
+            AstExpressionKind::StructLiteral {
+                ref name,
+                ref fields,
+                close_brace: _,
+            } => {
+                write!(self.writer, "{} {{", name.kind)?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    write!(
+                        self.writer,
+                        "{}{}: ",
+                        if i > 0 { ", " } else { " " },
+                        field.kind
+                    )?;
+                    self.visit_expression(value)?;
+                }
+                write!(
+                    self.writer,
+                    "{}",
+                    if fields.is_empty() { "}" } else { " }" }
+                )?;
+            }
+            AstExpressionKind::FieldAccess {
+                ref operand,
+                ref name_token,
+            } => {
+                self.visit_operand(operand)?;
+                write!(self.writer, ".{}", name_token.kind)?;
+            }
+            AstExpressionKind::Index {
+                ref operand,
+                ref index,
+                close_bracket: _,
+            } => {
+                self.visit_operand(operand)?;
+                write!(self.writer, "[")?;
+                self.visit_expression(index)?;
+                write!(self.writer, "]")?;
+            }
+            AstExpressionKind::Closure {
+                ref arguments,
+                ref statements,
+                close_brace: _,
+            } => {
+                write!(self.writer, "{{")?;
+                for (i, argument) in arguments.iter().enumerate() {
+                    write!(self.writer, "{}", if i > 0 { ", " } else { " " })?;
+                    self.visit_pattern(argument)?;
+                }
+                if !arguments.is_empty() {
+                    write!(self.writer, " ->")?;
+                }
+                writeln!(self.writer)?;
+                self.indent += 1;
+                for statement in statements {
+                    self.visit_ast(statement)?;
+                }
+                self.indent -= 1;
+                self.print_indent()?;
+                write!(self.writer, "}}")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_pattern(&mut self, pattern: &AstPattern) -> Result<()> {
+        match pattern.kind {
+            AstPatternKind::Let {
+                ref name_token,
+                ref typ,
+            } => {
+                write!(self.writer, "{}", name_token.kind)?;
+                if let Some(typ) = typ {
+                    write!(self.writer, ": ")?;
+                    self.visit_expression(typ)?;
+                }
+            }
+            AstPatternKind::Wildcard => write!(self.writer, "_")?,
+            AstPatternKind::Integer(value) => write!(
+                self.writer,
+                "{}",
+                IntegerLiteral {
+                    value,
+                    format: self.integer_format,
+                }
+            )?,
+            AstPatternKind::Range {
+                start,
+                end,
+                inclusive,
+            } => write!(
+                self.writer,
+                "{}{}{}",
+                IntegerLiteral {
+                    value: start,
+                    format: self.integer_format,
+                },
+                if inclusive { "..=" } else { ".." },
+                IntegerLiteral {
+                    value: end,
+                    format: self.integer_format,
+                }
+            )?,
+            AstPatternKind::Boolean(value) => write!(self.writer, "{value}")?,
+            AstPatternKind::Tuple(ref elements) => {
+                write!(self.writer, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.writer, ", ")?;
+                    }
+                    self.visit_pattern(element)?;
+                }
+                // a single element without the comma would just be in parentheses
+                write!(
+                    self.writer,
+                    "{}",
+                    if elements.len() == 1 { ",)" } else { ")" }
+                )?;
+            }
+            AstPatternKind::Binding {
+                ref name,
+                ref pattern,
+            } => {
+                write!(self.writer, "{} @ ", name.kind)?;
+                self.visit_pattern(pattern)?;
+            }
         }
+        Ok(())
     }
-    Ok(())
+}
+
+pub fn pretty_print_ast(
+    ast: &Ast,
+    indent: usize,
+    writer: &mut (impl Write + ?Sized),
+) -> Result<()> {
+    PrettyPrinter::new(writer, indent).visit_ast(ast)
+}
+
+pub fn pretty_print_ast_expression(
+    expression: &AstExpression,
+    indent: usize,
+    writer: &mut (impl Write + ?Sized),
+) -> Result<()> {
+    PrettyPrinter::new(writer, indent).visit_expression(expression)
 }
 
 pub fn pretty_print_ast_pattern(
@@ -111,27 +1086,43 @@ pub fn pretty_print_ast_pattern(
     indent: usize,
     writer: &mut (impl Write + ?Sized),
 ) -> Result<()> {
-    match pattern.kind {
-        AstPatternKind::Let {
-            ref name_token,
-            ref typ,
-        } => {
-            let TokenKind::Name(name) = name_token.kind else {
-                unreachable!();
-            };
-            write!(writer, "{name}")?;
-            if let Some(typ) = typ {
-                write!(writer, ": ")?;
-                pretty_print_ast_expression(typ, indent, writer)?;
-            }
-        }
-    }
-    Ok(())
+    PrettyPrinter::new(writer, indent).visit_pattern(pattern)
+}
+
+/// Whether `expression` is printed starting with a block-like expression that is not all of it
+fn starts_with_block_like(expression: &AstExpression) -> bool {
+    let first = match expression.kind {
+        AstExpressionKind::Call { ref operand, .. }
+        | AstExpressionKind::FieldAccess { ref operand, .. }
+        | AstExpressionKind::Index { ref operand, .. } => operand,
+        AstExpressionKind::Range {
+            start: Some(ref start),
+            ..
+        } => start,
+        _ => return false,
+    };
+    first.is_block_like() || starts_with_block_like(first)
 }
 
-fn print_indent(indent: usize, writer: &mut (impl Write + ?Sized)) -> Result<()> {
-    for _ in 0..indent {
-        write!(writer, "    ")?;
+/// Whether a struct literal in `expression` is not already inside parentheses, brackets or braces
+fn has_exterior_struct_literal(expression: &AstExpression) -> bool {
+    match expression.kind {
+        AstExpressionKind::StructLiteral { .. } => true,
+        AstExpressionKind::Unary { ref operand, .. }
+        | AstExpressionKind::Call { ref operand, .. }
+        | AstExpressionKind::FieldAccess { ref operand, .. }
+        | AstExpressionKind::Index { ref operand, .. } => has_exterior_struct_literal(operand),
+        AstExpressionKind::Binary {
+            ref left,
+            ref right,
+            ..
+        } => has_exterior_struct_literal(left) || has_exterior_struct_literal(right),
+        AstExpressionKind::Range {
+            ref start, ref end, ..
+        } => [start, end]
+            .into_iter()
+            .flatten()
+            .any(|bound| has_exterior_struct_literal(bound)),
+        _ => false,
     }
-    Ok(())
 }
@@ -0,0 +1,89 @@
+use crate::{
+    ast::{Ast, AstKind},
+    interning::InternedStr,
+    lexer::{Location, TokenKind},
+};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+/// A global item, identified by its index in the items it was declared in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeclId(pub usize);
+
+#[derive(Debug, Error)]
+pub enum ResolveErrorKind {
+    #[error("'{name}' is already defined at {first}")]
+    DuplicateDefinition { name: InternedStr, first: Location },
+}
+
+impl ResolveErrorKind {
+    /// The name of the variant, which stays the same when the message is reworded
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ResolveErrorKind::DuplicateDefinition { .. } => "DuplicateDefinition",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("{location}: {kind}")]
+pub struct ResolveError {
+    pub kind: ResolveErrorKind,
+    pub location: Location,
+}
+
+/// The name of every global item, where the first of several items with the same name is the one that is used
+pub fn global_declarations(items: &[Ast]) -> FxHashMap<InternedStr, DeclId> {
+    let mut declarations = FxHashMap::default();
+    for (index, item) in items.iter().enumerate() {
+        let (AstKind::Function { ref name, .. }
+        | AstKind::ExternFunction { ref name, .. }
+        | AstKind::Struct { ref name, .. }
+        | AstKind::Enum { ref name, .. }) = item.kind
+        else {
+            continue;
+        };
+        let TokenKind::Name(name) = name.kind else {
+            unreachable!();
+        };
+        declarations.entry(name).or_insert(DeclId(index));
+    }
+    declarations
+}
+
+/// Checks that no two global items have the same name, reporting every later definition against the first one
+///
+/// All items share a single namespace, so a function and any future kind of item cannot have the same name either
+pub fn check_global_names(items: &[Ast]) -> Result<(), Vec<ResolveError>> {
+    let mut definitions = FxHashMap::<InternedStr, Location>::default();
+    let mut errors = vec![];
+    for item in items {
+        let name_token = match item.kind {
+            AstKind::Function { ref name, .. }
+            | AstKind::ExternFunction { ref name, .. }
+            | AstKind::Struct { ref name, .. }
+            | AstKind::Enum { ref name, .. } => name,
+            AstKind::Expression(_)
+            | AstKind::Let { .. }
+            | AstKind::Return { .. }
+            | AstKind::For { .. }
+            | AstKind::Assignment { .. } => continue,
+        };
+        let TokenKind::Name(name) = name_token.kind else {
+            unreachable!();
+        };
+        if let Some(&first) = definitions.get(&name) {
+            errors.push(ResolveError {
+                kind: ResolveErrorKind::DuplicateDefinition { name, first },
+                location: name_token.location,
+            });
+        } else {
+            definitions.insert(name, name_token.location);
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
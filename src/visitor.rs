@@ -0,0 +1,271 @@
+use crate::ast::{Ast, AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind};
+
+pub trait Visitor {
+    type Error;
+
+    fn visit_ast(&mut self, ast: &Ast) -> Result<(), Self::Error> {
+        walk_ast(self, ast)
+    }
+
+    fn visit_expression(&mut self, expression: &AstExpression) -> Result<(), Self::Error> {
+        walk_expression(self, expression)
+    }
+
+    fn visit_pattern(&mut self, pattern: &AstPattern) -> Result<(), Self::Error> {
+        walk_pattern(self, pattern)
+    }
+}
+
+pub fn walk_ast<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast) -> Result<(), V::Error> {
+    match ast.kind {
+        AstKind::Expression(ref expression) => visitor.visit_expression(expression)?,
+        AstKind::Let {
+            ref pattern,
+            equals: _,
+            ref value,
+        } => {
+            visitor.visit_pattern(pattern)?;
+            visitor.visit_expression(value)?;
+        }
+        AstKind::Function {
+            doc: _,
+            attributes: _,
+            name: _,
+            generics: _,
+            ref arguments,
+            ref return_type,
+            ref where_clause,
+            ref body,
+        } => {
+            for argument in arguments {
+                visitor.visit_pattern(argument)?;
+            }
+            if let Some(return_type) = return_type {
+                visitor.visit_expression(return_type)?;
+            }
+            for (_, bound) in where_clause {
+                visitor.visit_expression(bound)?;
+            }
+            visitor.visit_expression(body)?;
+        }
+        AstKind::ExternFunction {
+            doc: _,
+            attributes: _,
+            name: _,
+            ref arguments,
+            ref return_type,
+        } => {
+            for argument in arguments {
+                visitor.visit_pattern(argument)?;
+            }
+            if let Some(return_type) = return_type {
+                visitor.visit_expression(return_type)?;
+            }
+        }
+        AstKind::Struct {
+            doc: _,
+            attributes: _,
+            name: _,
+            ref fields,
+        } => {
+            for (_, typ) in fields {
+                visitor.visit_expression(typ)?;
+            }
+        }
+        AstKind::Enum {
+            doc: _,
+            attributes: _,
+            name: _,
+            ref variants,
+        } => {
+            for (_, payload) in variants {
+                for typ in payload {
+                    visitor.visit_expression(typ)?;
+                }
+            }
+        }
+        AstKind::For {
+            ref pattern,
+            ref iterable,
+            ref body,
+        } => {
+            visitor.visit_pattern(pattern)?;
+            visitor.visit_expression(iterable)?;
+            visitor.visit_expression(body)?;
+        }
+        AstKind::Return { ref expression } => visitor.visit_expression(expression)?,
+        AstKind::Assignment {
+            ref target,
+            equals: _,
+            ref value,
+        } => {
+            visitor.visit_expression(target)?;
+            visitor.visit_expression(value)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    expression: &AstExpression,
+) -> Result<(), V::Error> {
+    match expression.kind {
+        AstExpressionKind::Name(_) => {}
+        AstExpressionKind::Integer(_)
+        | AstExpressionKind::Float(_)
+        | AstExpressionKind::String(_)
+        | AstExpressionKind::Char(_)
+        | AstExpressionKind::Boolean(_)
+        | AstExpressionKind::Unit => {}
+        AstExpressionKind::Unary {
+            operator: _,
+            ref operand,
+        } => visitor.visit_expression(operand)?,
+        AstExpressionKind::Binary {
+            ref left,
+            operator: _,
+            ref right,
+        } => {
+            visitor.visit_expression(left)?;
+            visitor.visit_expression(right)?;
+        }
+        AstExpressionKind::Range {
+            ref start,
+            ref end,
+            inclusive: _,
+        } => {
+            if let Some(start) = start {
+                visitor.visit_expression(start)?;
+            }
+            if let Some(end) = end {
+                visitor.visit_expression(end)?;
+            }
+        }
+        AstExpressionKind::Array {
+            ref elements,
+            close_bracket: _,
+        } => {
+            for element in elements {
+                visitor.visit_expression(element)?;
+            }
+        }
+        AstExpressionKind::ArrayRepeat {
+            ref value,
+            ref count,
+            close_bracket: _,
+        } => {
+            visitor.visit_expression(value)?;
+            visitor.visit_expression(count)?;
+        }
+        AstExpressionKind::Block {
+            ref statements,
+            close_brace: _,
+        } => {
+            for statement in statements {
+                visitor.visit_ast(statement)?;
+            }
+        }
+        AstExpressionKind::If {
+            ref condition,
+            ref then_block,
+            ref else_block,
+        } => {
+            visitor.visit_expression(condition)?;
+            visitor.visit_expression(then_block)?;
+            if let Some(else_block) = else_block {
+                visitor.visit_expression(else_block)?;
+            }
+        }
+        AstExpressionKind::Match {
+            ref scrutinee,
+            ref arms,
+            close_brace: _,
+        } => {
+            visitor.visit_expression(scrutinee)?;
+            for (pattern, value) in arms {
+                visitor.visit_pattern(pattern)?;
+                visitor.visit_expression(value)?;
+            }
+        }
+        AstExpressionKind::Call {
+            ref operand,
+            ref arguments,
+            commas: _,
+            close_parenthesis: _,
+        } => {
+            visitor.visit_expression(operand)?;
+            for argument in arguments {
+                visitor.visit_expression(argument)?;
+            }
+        }
+        AstExpressionKind::StructLiteral {
+            name: _,
+            ref fields,
+            close_brace: _,
+        } => {
+            for (_, value) in fields {
+                visitor.visit_expression(value)?;
+            }
+        }
+        AstExpressionKind::FieldAccess {
+            ref operand,
+            name_token: _,
+        } => visitor.visit_expression(operand)?,
+        AstExpressionKind::Index {
+            ref operand,
+            ref index,
+            close_bracket: _,
+        } => {
+            visitor.visit_expression(operand)?;
+            visitor.visit_expression(index)?;
+        }
+        AstExpressionKind::Closure {
+            ref arguments,
+            ref statements,
+            close_brace: _,
+        } => {
+            for argument in arguments {
+                visitor.visit_pattern(argument)?;
+            }
+            for statement in statements {
+                visitor.visit_ast(statement)?;
+            }
+        }
+        AstExpressionKind::Paren {
+            ref inner,
+            close_parenthesis: _,
+        } => visitor.visit_expression(inner)?,
+    }
+    Ok(())
+}
+
+pub fn walk_pattern<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    pattern: &AstPattern,
+) -> Result<(), V::Error> {
+    match pattern.kind {
+        AstPatternKind::Let {
+            name_token: _,
+            ref typ,
+        } => {
+            if let Some(typ) = typ {
+                visitor.visit_expression(typ)?;
+            }
+        }
+        AstPatternKind::Wildcard
+        | AstPatternKind::Integer(_)
+        | AstPatternKind::Range { .. }
+        | AstPatternKind::Boolean(_) => {}
+        AstPatternKind::Tuple(ref elements) => {
+            for element in elements {
+                visitor.visit_pattern(element)?;
+            }
+        }
+        AstPatternKind::Binding {
+            name: _,
+            ref pattern,
+        } => visitor.visit_pattern(pattern)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use lang::{
+    analysis::{analyze, free_variables, Diagnostic},
+    parsing::{parse, parse_expression_str},
+};
+
+fn free(source: &str) -> Vec<&'static str> {
+    let expression = parse_expression_str("test".into(), source).unwrap();
+    let mut names = free_variables(&expression)
+        .into_iter()
+        .map(|name| name.to_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn free_variables_of_an_operator() {
+    assert_eq!(free("x + y"), ["x", "y"]);
+}
+
+#[test]
+fn let_binds_the_rest_of_the_block() {
+    assert_eq!(free("{ let x = 1; x + y; }"), ["y"]);
+    assert_eq!(free("{ x; let x = 1; }"), ["x"]);
+}
+
+#[test]
+fn closure_arguments_are_bound() {
+    assert_eq!(free("each(xs) { x -> x + y; }"), ["each", "xs", "y"]);
+}
+
+#[test]
+fn analysis_reports_errors_and_warnings_sorted_by_position() {
+    let source = "fn main() { let x = 007; let y = 08; }\nfn main() {}\n";
+    let asts = parse("test".into(), source).unwrap();
+    let analysis = analyze("test".into(), source, &asts);
+    let codes = |diagnostics: &[Diagnostic]| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.location.position))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        codes(&analysis.errors),
+        [("DuplicateDefinition", source.rfind("main").unwrap())]
+    );
+    assert_eq!(
+        codes(&analysis.warnings),
+        [
+            ("LeadingZeros", source.find("007").unwrap()),
+            ("LeadingZeros", source.find("08").unwrap()),
+            // only the first `main` is the entry point
+            ("DeadFunction", source.rfind("main").unwrap()),
+        ]
+    );
+}
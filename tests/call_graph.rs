@@ -0,0 +1,35 @@
+use lang::{
+    call_graph::call_graph,
+    parsing::parse,
+    resolve::{global_declarations, DeclId},
+};
+
+const PROGRAM: &str = "
+fn main() { foo(1); }
+fn foo(x) { return double(x); }
+fn double(x) { return x * 2; }
+fn bar() { foo(2); }
+";
+
+#[test]
+fn calls_are_resolved_to_declarations() {
+    let asts = parse("test".into(), PROGRAM).unwrap();
+    let declarations = global_declarations(&asts);
+    let id = |name: &str| declarations[&name.into()];
+    let graph = call_graph(&asts);
+    assert_eq!(graph.callees(id("main")).collect::<Vec<_>>(), [id("foo")]);
+    assert_eq!(graph.callees(id("foo")).collect::<Vec<_>>(), [id("double")]);
+    assert_eq!(graph.callees(id("double")).count(), 0);
+    assert_eq!(graph.callees(id("bar")).collect::<Vec<_>>(), [id("foo")]);
+}
+
+#[test]
+fn unreferenced_function_is_unreachable_from_main() {
+    let asts = parse("test".into(), PROGRAM).unwrap();
+    let mut reachable = call_graph(&asts)
+        .reachable_from(DeclId(0))
+        .into_iter()
+        .collect::<Vec<_>>();
+    reachable.sort();
+    assert_eq!(reachable, [DeclId(0), DeclId(1), DeclId(2)]);
+}
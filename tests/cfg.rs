@@ -0,0 +1,47 @@
+use lang::{ast::AstKind, cfg::filter_cfg, interning::InternedStr, parsing::parse};
+use std::collections::HashSet;
+
+fn item_names(source: &str, active: &[&str]) -> Vec<String> {
+    let active = active
+        .iter()
+        .map(|&name| InternedStr::from(name))
+        .collect::<HashSet<_>>();
+    let asts = parse("test".into(), source).unwrap();
+    filter_cfg(asts, &active)
+        .iter()
+        .map(|ast| match ast.kind {
+            AstKind::Function { ref name, .. }
+            | AstKind::ExternFunction { ref name, .. }
+            | AstKind::Struct { ref name, .. }
+            | AstKind::Enum { ref name, .. } => name.kind.to_string(),
+            _ => panic!("expected an item"),
+        })
+        .collect()
+}
+
+#[test]
+fn inactive_cfg_filters_out_a_function() {
+    let source = "#[cfg(a)] fn a() {} #[cfg(b)] fn b() {} #[inline] fn c() {}";
+    assert_eq!(item_names(source, &["a"]), ["a", "c"]);
+    assert_eq!(item_names(source, &[]), ["c"]);
+}
+
+#[test]
+fn cfg_filters_structs() {
+    let source = "/** doc */ #[cfg(a)] struct A {} #[cfg(not(a))] struct B {}";
+    assert_eq!(item_names(source, &["a"]), ["A"]);
+    assert_eq!(item_names(source, &[]), ["B"]);
+}
+
+#[test]
+fn cfg_filters_extern_functions() {
+    let source = "#[cfg(a)] extern fn a(); #[cfg(any())] extern fn b();";
+    assert_eq!(item_names(source, &["a"]), ["a"]);
+}
+
+#[test]
+fn cfg_filters_enums() {
+    let source = "/** doc */ #[cfg(all(a, b))] enum E {} #[cfg(a)] enum F { X }";
+    assert_eq!(item_names(source, &["a"]), ["F"]);
+    assert_eq!(item_names(source, &["a", "b"]), ["E", "F"]);
+}
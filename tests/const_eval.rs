@@ -0,0 +1,67 @@
+use lang::{
+    const_eval::{eval_const, ConstEnv, ConstEvalErrorKind},
+    interpreter::{RuntimeErrorKind, Value},
+    lexer::{tokenize, LexerErrorKind, TokenKind},
+    parsing::parse_expression_str,
+};
+
+fn eval(source: &str) -> Result<Value, ConstEvalErrorKind> {
+    let expression = parse_expression_str("test".into(), source).unwrap();
+    eval_const(&expression, &ConstEnv::default()).map_err(|error| error.kind)
+}
+
+#[test]
+fn arithmetic_is_constant() {
+    assert_eq!(eval("2 + 3 * 4").unwrap(), Value::Integer(14));
+}
+
+#[test]
+fn calls_are_not_constant() {
+    assert!(matches!(
+        eval("foo()"),
+        Err(ConstEvalErrorKind::NotConstant)
+    ));
+}
+
+#[test]
+fn right_operand_is_only_evaluated_when_needed() {
+    assert_eq!(eval("false && (1 / 0 == 0)").unwrap(), Value::Bool(false));
+    assert!(matches!(
+        eval("true && (1 / 0 == 0)"),
+        Err(ConstEvalErrorKind::Arithmetic(
+            RuntimeErrorKind::DivisionByZero
+        ))
+    ));
+}
+
+#[test]
+fn other_literals_are_not_constant() {
+    for source in ["1.5", "\"text\"", "'c'", "1 + 1.5"] {
+        assert!(
+            matches!(eval(source), Err(ConstEvalErrorKind::NotConstant)),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn every_base_gives_the_same_value() {
+    for source in ["255", "0xFF", "0xff", "0o377", "0b1111_1111", "0d255"] {
+        assert_eq!(
+            tokenize("test".into(), source).unwrap()[0].kind,
+            TokenKind::Integer(255),
+            "{source}"
+        );
+        assert_eq!(
+            eval(&format!("{source} - 0xF0 * 0b1")).unwrap(),
+            Value::Integer(15)
+        );
+    }
+    assert_eq!(eval("0d10 == 10").unwrap(), Value::Bool(true));
+    let error = tokenize("test".into(), "0b12").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        LexerErrorKind::DigitTooLarge { base: 2 }
+    ));
+    assert_eq!(error.location.position, 3);
+}
@@ -0,0 +1,35 @@
+use lang::{
+    diff::{ast_diff, expression_diff, structurally_eq, AstDiff},
+    parsing::{parse, parse_expression_str},
+};
+
+#[test]
+fn diff_reports_the_path_to_the_divergence() {
+    let a = parse("test".into(), "fn f() { let x = 1; g(); return 2 * 3; }").unwrap();
+    let b = parse(
+        "test".into(),
+        "fn f() {\n    let x = 1;\n    g();\n    return 2 * 4;\n}",
+    )
+    .unwrap();
+    assert_eq!(
+        ast_diff(&a[0], &b[0]),
+        [AstDiff {
+            path: vec![
+                "function body".into(),
+                "statement[2]".into(),
+                "return value".into(),
+                "binary right operand".into(),
+            ],
+            left: "Integer(3)".into(),
+            right: "Integer(4)".into(),
+        }]
+    );
+    assert!(!structurally_eq(&a[0], &b[0]));
+}
+
+#[test]
+fn locations_are_ignored() {
+    let a = parse_expression_str("test".into(), "f(a, b)").unwrap();
+    let b = parse_expression_str("other".into(), "f( a ,b )").unwrap();
+    assert!(expression_diff(&a, &b).is_empty());
+}
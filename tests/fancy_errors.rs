@@ -0,0 +1,22 @@
+#![cfg(feature = "fancy-errors")]
+
+use ariadne::Source;
+use lang::parsing::parse;
+
+#[test]
+fn unexpected_token_report_has_a_label() {
+    let source = "fn f() { let x = 1 }";
+    let error = parse("test.lang".into(), source).unwrap_err();
+    let mut output = vec![];
+    error
+        .report(source)
+        .write(("test.lang", Source::from(source)), &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Unexpected token '}'"), "{output}");
+    assert!(
+        output.contains("this token was not expected here"),
+        "{output}"
+    );
+    assert!(output.contains("test.lang:1:20"), "{output}");
+}
@@ -0,0 +1,57 @@
+use lang::{
+    ast::{AstExpression, AstExpressionKind, AstPattern, AstPatternKind},
+    diff::structurally_eq,
+    fold::{fold_expression, fold_pattern, Folder},
+    normalize::ConstantFolder,
+    parsing::parse_statement_str,
+};
+
+struct Doubler;
+
+impl Folder for Doubler {
+    fn fold_expression(&mut self, expression: AstExpression) -> AstExpression {
+        let mut expression = fold_expression(self, expression);
+        if let AstExpressionKind::Integer(ref mut value) = expression.kind {
+            *value *= 2;
+        }
+        expression
+    }
+
+    fn fold_pattern(&mut self, pattern: AstPattern) -> AstPattern {
+        let mut pattern = fold_pattern(self, pattern);
+        if let AstPatternKind::Integer(ref mut value) = pattern.kind {
+            *value *= 2;
+        }
+        pattern
+    }
+}
+
+fn assert_folds_to(folder: &mut impl Folder, source: &str, expected: &str) {
+    let folded = folder.fold_ast(parse_statement_str("test".into(), source).unwrap());
+    let expected = parse_statement_str("test".into(), expected).unwrap();
+    assert!(
+        structurally_eq(&folded, &expected),
+        "{source} did not fold to {expected:?}, got {folded:?}"
+    );
+}
+
+#[test]
+fn folder_doubles_every_integer_literal() {
+    assert_folds_to(
+        &mut Doubler,
+        "let x = f(1, [2; 3]) + { 4; };",
+        "let x = f(2, [4; 6]) + { 8; };",
+    );
+    assert_folds_to(
+        &mut Doubler,
+        "match x { 1 => 2, _ => 3 };",
+        "match x { 2 => 4, _ => 6 };",
+    );
+}
+
+#[test]
+fn constant_folder_folds_literal_operators() {
+    assert_folds_to(&mut ConstantFolder, "f(1 + 2 * 3, x + 1);", "f(7, x + 1);");
+    assert_folds_to(&mut ConstantFolder, "1 - 2;", "-1;");
+    assert_folds_to(&mut ConstantFolder, "1 / 0;", "1 / 0;");
+}
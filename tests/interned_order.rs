@@ -0,0 +1,13 @@
+use lang::interning::InternedStr;
+
+#[test]
+fn interned_strings_sort_alphabetically() {
+    // interned in an order that is not alphabetical, so sorting by the interner's keys would be wrong
+    let mut names = ["cherry", "banana", "apple"].map(InternedStr::intern);
+    names.sort();
+    assert_eq!(
+        names.map(InternedStr::to_str),
+        ["apple", "banana", "cherry"]
+    );
+    assert!(InternedStr::intern("banana") > InternedStr::intern("apple"));
+}
@@ -0,0 +1,46 @@
+use lang::{
+    interning::{init_with_capacity, seed, InternedStr},
+    lexer::{tokenize, TokenKind},
+};
+
+/// The interner is shared by the whole process, so this has to be the only test in this file
+#[test]
+fn presized_and_seeded_interner() {
+    assert!(init_with_capacity(1 << 16));
+    assert!(
+        !init_with_capacity(1 << 16),
+        "the interner can only be created once"
+    );
+
+    assert_eq!(InternedStr::get("seeded"), None);
+    seed(&["seeded", "also_seeded"]);
+    assert_eq!(
+        InternedStr::get("seeded").map(InternedStr::to_str),
+        Some("seeded")
+    );
+    assert!(InternedStr::get("also_seeded").is_some());
+
+    let count = 10_000;
+    let source = (0..count)
+        .map(|i| format!("fn f{i}(a{i}) {{ return a{i} + seeded; }}\n"))
+        .collect::<String>();
+    let names = tokenize("big.lang".into(), &source)
+        .unwrap()
+        .into_iter()
+        .filter_map(|token| match token.kind {
+            TokenKind::Name(name) => Some(name.to_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let expected = (0..count)
+        .flat_map(|i| {
+            [
+                format!("f{i}"),
+                format!("a{i}"),
+                format!("a{i}"),
+                "seeded".into(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, expected);
+}
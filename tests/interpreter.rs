@@ -0,0 +1,213 @@
+use lang::{
+    ast::AstKind,
+    const_eval::{eval_const, ConstEnv},
+    interpreter::{eval_expression, eval_expression_in, Environment, RuntimeErrorKind, Value},
+    parsing::{parse, parse_expression_str},
+};
+
+fn add(arguments: &[Value]) -> Result<Value, RuntimeErrorKind> {
+    match *arguments {
+        [Value::Integer(a), Value::Integer(b)] => Ok(Value::Integer(a + b)),
+        _ => Err(RuntimeErrorKind::Unsupported),
+    }
+}
+
+#[test]
+fn extern_fn_calls_registered_builtin() {
+    let asts = parse(
+        "test".into(),
+        "/** Adds two integers */\n#[inline]\nextern fn add(a, b);",
+    )
+    .unwrap();
+    let AstKind::ExternFunction {
+        doc,
+        ref attributes,
+        ..
+    } = asts[0].kind
+    else {
+        panic!("expected an extern function");
+    };
+    assert_eq!(doc.map(|doc| doc.to_str()), Some("Adds two integers"));
+    assert_eq!(attributes.len(), 1);
+
+    let mut environment = Environment::new();
+    environment.register_builtin("add".into(), add);
+    environment.define_externs(&asts).unwrap();
+    let expression = parse_expression_str("test".into(), "add(1, 2)").unwrap();
+    assert_eq!(
+        eval_expression_in(&expression, &environment).unwrap(),
+        Value::Integer(3)
+    );
+}
+
+#[test]
+fn calling_an_unregistered_builtin_is_an_error() {
+    let mut environment = Environment::new();
+    environment.define("missing".into(), Value::Builtin("missing".into()));
+    let expression = parse_expression_str("test".into(), "missing(1)").unwrap();
+    assert!(matches!(
+        eval_expression_in(&expression, &environment).unwrap_err().kind,
+        RuntimeErrorKind::UnregisteredBuiltin(name) if name.to_str() == "missing"
+    ));
+}
+
+#[test]
+fn standalone_arithmetic() {
+    let expression = parse_expression_str("test".into(), "1 + 2 * 3").unwrap();
+    assert_eq!(eval_expression(&expression).unwrap(), Value::Integer(7));
+    let expression = parse_expression_str("test".into(), "10 / 0").unwrap();
+    assert!(matches!(
+        eval_expression(&expression).unwrap_err().kind,
+        RuntimeErrorKind::DivisionByZero
+    ));
+    let expression = parse_expression_str("test".into(), "x + 1").unwrap();
+    assert!(matches!(
+        eval_expression(&expression).unwrap_err().kind,
+        RuntimeErrorKind::UndefinedName(_)
+    ));
+}
+
+fn boom(_: &[Value]) -> Result<Value, RuntimeErrorKind> {
+    panic!("the right operand should not have been evaluated");
+}
+
+fn eval_with_boom(source: &str) -> Value {
+    let mut environment = Environment::new();
+    environment.register_builtin("boom".into(), boom);
+    environment
+        .define_externs(&parse("test".into(), "extern fn boom();").unwrap())
+        .unwrap();
+    let expression = parse_expression_str("test".into(), source).unwrap();
+    eval_expression_in(&expression, &environment).unwrap()
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    assert_eq!(eval_with_boom("false && boom()"), Value::Bool(false));
+    assert_eq!(eval_with_boom("true || boom()"), Value::Bool(true));
+    assert_eq!(
+        eval_with_boom("1 == 2 && boom() && boom()"),
+        Value::Bool(false)
+    );
+}
+
+#[test]
+fn logical_operators_evaluate_the_right_operand_when_needed() {
+    let expression = parse_expression_str("test".into(), "true && 1 == 1").unwrap();
+    assert_eq!(
+        eval_expression_in(&expression, &Environment::new()).unwrap(),
+        Value::Bool(true)
+    );
+    let expression = parse_expression_str("test".into(), "false || false").unwrap();
+    assert_eq!(
+        eval_expression_in(&expression, &Environment::new()).unwrap(),
+        Value::Bool(false)
+    );
+}
+
+#[test]
+fn both_evaluators_skip_a_failing_right_operand() {
+    for (source, value) in [("false && 1 / 0 == 0", false), ("true || 1 / 0 == 0", true)] {
+        let expression = parse_expression_str("test".into(), source).unwrap();
+        assert_eq!(
+            eval_expression_in(&expression, &Environment::new()).unwrap(),
+            Value::Bool(value)
+        );
+        assert_eq!(
+            eval_const(&expression, &ConstEnv::default()).unwrap(),
+            Value::Bool(value)
+        );
+    }
+}
+
+fn eval(source: &str) -> Result<Value, RuntimeErrorKind> {
+    let expression = parse_expression_str("test".into(), source).unwrap();
+    eval_expression_in(&expression, &Environment::new()).map_err(|error| error.kind)
+}
+
+#[test]
+fn min_integer_literal() {
+    assert_eq!(
+        eval("-9223372036854775808").unwrap(),
+        Value::Integer(i64::MIN)
+    );
+    assert_eq!(
+        eval("-9223372036854775808 + 1").unwrap(),
+        Value::Integer(i64::MIN + 1)
+    );
+    assert!(matches!(
+        eval("9223372036854775808"),
+        Err(RuntimeErrorKind::IntegerOverflow)
+    ));
+    assert!(matches!(
+        eval("- -9223372036854775808"),
+        Err(RuntimeErrorKind::IntegerOverflow)
+    ));
+
+    let expression = parse_expression_str("test".into(), "-9223372036854775808").unwrap();
+    assert_eq!(
+        eval_const(&expression, &ConstEnv::default()).unwrap(),
+        Value::Integer(i64::MIN)
+    );
+}
+
+#[test]
+fn integers_compare_by_value() {
+    assert_eq!(eval("1 < 2").unwrap(), Value::Bool(true));
+    assert_eq!(eval("2 <= 1").unwrap(), Value::Bool(false));
+    assert_eq!(eval("3 == 3").unwrap(), Value::Bool(true));
+}
+
+#[test]
+fn booleans_only_compare_for_equality() {
+    assert_eq!(eval("true == true").unwrap(), Value::Bool(true));
+    assert_eq!(eval("true != false").unwrap(), Value::Bool(true));
+    assert!(matches!(
+        eval("false < true"),
+        Err(RuntimeErrorKind::NotOrderable("bool"))
+    ));
+}
+
+#[test]
+fn values_of_different_types_do_not_compare() {
+    assert!(matches!(
+        eval("1 < true"),
+        Err(RuntimeErrorKind::TypeMismatch {
+            left: "int",
+            right: "bool"
+        })
+    ));
+    assert!(matches!(
+        eval("1 == true"),
+        Err(RuntimeErrorKind::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn local_shadows_a_global_until_its_scope_pops() {
+    let x = "x".into();
+    let mut environment = Environment::new();
+    environment.define(x, Value::Integer(1));
+    environment.push_scope();
+    environment.define(x, Value::Integer(2));
+    assert_eq!(environment.lookup(x), Some(&Value::Integer(2)));
+    environment.push_scope();
+    assert_eq!(environment.lookup(x), Some(&Value::Integer(2)));
+    environment.pop_scope();
+    environment.pop_scope();
+    assert_eq!(environment.lookup(x), Some(&Value::Integer(1)));
+}
+
+#[test]
+fn function_bodies_only_see_globals() {
+    let (x, y) = ("x".into(), "y".into());
+    let mut environment = Environment::new();
+    environment.define_global(x, Value::Integer(1));
+    environment.push_scope();
+    environment.define(y, Value::Integer(2));
+    let caller_scopes = environment.enter_function();
+    assert_eq!(environment.lookup(x), Some(&Value::Integer(1)));
+    assert_eq!(environment.lookup(y), None);
+    environment.exit_function(caller_scopes);
+    assert_eq!(environment.lookup(y), Some(&Value::Integer(2)));
+}
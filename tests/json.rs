@@ -0,0 +1,159 @@
+use lang::{
+    analysis::analyze,
+    json::{ast_to_json, write_diagnostics_jsonl},
+    parsing::{parse, parse_statement_str},
+};
+
+/// Checks that `json` is a single JSON value, returning the rest of the input after it
+fn skip_value(json: &str) -> Result<&str, String> {
+    let json = json.trim_start();
+    let rest = if let Some(mut rest) = json.strip_prefix('{') {
+        rest = rest.trim_start();
+        if let Some(rest) = rest.strip_prefix('}') {
+            return Ok(rest);
+        }
+        loop {
+            rest = skip_string(rest.trim_start())?;
+            rest = rest.trim_start().strip_prefix(':').ok_or("expected ':'")?;
+            rest = skip_value(rest)?.trim_start();
+            match rest.strip_prefix(',') {
+                Some(after) => rest = after,
+                None => break rest.strip_prefix('}').ok_or("expected '}'")?,
+            }
+        }
+    } else if let Some(mut rest) = json.strip_prefix('[') {
+        if let Some(rest) = rest.trim_start().strip_prefix(']') {
+            return Ok(rest);
+        }
+        loop {
+            rest = skip_value(rest)?.trim_start();
+            match rest.strip_prefix(',') {
+                Some(after) => rest = after,
+                None => break rest.strip_prefix(']').ok_or("expected ']'")?,
+            }
+        }
+    } else if json.starts_with('"') {
+        skip_string(json)?
+    } else if let Some(rest) = ["null", "true", "false"]
+        .iter()
+        .find_map(|literal| json.strip_prefix(literal))
+    {
+        rest
+    } else {
+        let rest = json.trim_start_matches(|c: char| c == '-' || c.is_ascii_digit());
+        if rest.len() == json.len() {
+            return Err(format!("unexpected {json:?}"));
+        }
+        rest
+    };
+    Ok(rest)
+}
+
+fn skip_string(json: &str) -> Result<&str, String> {
+    let mut chars = json
+        .strip_prefix('"')
+        .ok_or("expected a string")?
+        .char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok(&json[i + 2..]),
+            '\\' => match chars.next() {
+                Some((_, 'u')) => {
+                    for _ in 0..4 {
+                        chars
+                            .next()
+                            .filter(|(_, c)| c.is_ascii_hexdigit())
+                            .ok_or("invalid unicode escape")?;
+                    }
+                }
+                Some((_, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't')) => {}
+                _ => return Err("invalid escape".into()),
+            },
+            c if c.is_control() => return Err("unescaped control character".into()),
+            _ => {}
+        }
+    }
+    Err("unterminated string".into())
+}
+
+#[test]
+fn diagnostics_jsonl_has_one_object_per_line() {
+    let source = "fn main() {\n  let x = 007;\n}\nfn unused() {}\nfn unused() {}\n";
+    let asts = parse("test".into(), source).unwrap();
+    let analysis = analyze("test".into(), source, &asts);
+    assert!(!analysis.errors.is_empty());
+    assert!(analysis.warnings.len() >= 2);
+
+    let mut output = vec![];
+    write_diagnostics_jsonl(&analysis, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), analysis.errors.len() + analysis.warnings.len());
+    for line in lines {
+        assert!(line.starts_with('{'), "{line} is not an object");
+        let rest = skip_value(line).unwrap_or_else(|error| panic!("{line}: {error}"));
+        assert!(rest.is_empty(), "{line} has {rest:?} after the object");
+        assert!(line.contains("\"code\":\""), "{line} has no code");
+        assert!(line.contains("\"end\":{"), "{line} has no end");
+    }
+}
+
+#[test]
+fn diagnostics_have_a_code_and_an_end() {
+    let source = "fn main() {}\nfn leading() { 007; }\n";
+    let asts = parse("test".into(), source).unwrap();
+    let analysis = analyze("test".into(), source, &asts);
+    let leading_zeros = analysis
+        .warnings
+        .iter()
+        .find(|warning| warning.code == "LeadingZeros")
+        .expect("expected a leading zeros warning");
+    assert_eq!(
+        &source[leading_zeros.location.position..leading_zeros.end.position],
+        "007"
+    );
+    let dead_function = analysis
+        .warnings
+        .iter()
+        .find(|warning| warning.code == "DeadFunction")
+        .expect("expected a dead function warning");
+    assert!(dead_function.end.position > dead_function.location.position);
+}
+
+fn statement_json(source: &str, pretty: bool) -> String {
+    let ast = parse_statement_str("test".into(), source).unwrap();
+    let mut output = vec![];
+    ast_to_json(&[ast], &mut output, pretty).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn let_has_a_nested_integer_value() {
+    let json = statement_json("let x = 1;", false);
+    assert_eq!(skip_value(&json).map(str::trim_end), Ok(""));
+    let value = json
+        .find("\"value\":{\"kind\":\"Integer\",\"value\":1,")
+        .expect("expected an integer value");
+    assert!(json.starts_with("[{\"kind\":\"Let\","), "{json}");
+    assert!(json[value..].contains("\"location\":{\"filepath\":\"test\",\"position\":8,"));
+
+    let pretty = statement_json("let x = 1;", true);
+    assert_eq!(skip_value(&pretty).map(str::trim_end), Ok(""));
+    assert!(pretty.lines().count() > 1);
+    assert_eq!(
+        pretty.split_whitespace().collect::<String>(),
+        json.split_whitespace().collect::<String>()
+    );
+}
+
+#[test]
+fn multiplication_has_a_higher_precedence_than_addition() {
+    let json = statement_json("1 + 2 * 3;", false);
+    let precedence = |operator: &str| -> usize {
+        let field = format!("\"operator\":\"{operator}\",\"precedence\":");
+        let rest = &json[json.find(&field).expect("expected the operator") + field.len()..];
+        rest[..rest.find(',').unwrap()].parse().unwrap()
+    };
+    assert!(precedence("*") > precedence("+"));
+    assert!(json.contains("\"associativity\":\"left\""));
+}
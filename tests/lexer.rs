@@ -0,0 +1,407 @@
+use lang::lexer::{
+    highlight, tokenize, Lexer, LexerErrorKind, LexerOptions, Location, Token, TokenKind,
+};
+use std::num::NonZero;
+
+fn relex(before: &str, after: &str, offset: usize, options: &LexerOptions) -> Vec<Token> {
+    let mut lexer = Lexer::new_with_options("test".into(), before, options);
+    let mut previous = vec![];
+    loop {
+        let token = lexer.next_significant_token().unwrap();
+        let is_eof = matches!(token.kind, TokenKind::EOF);
+        previous.push(token);
+        if is_eof {
+            break;
+        }
+    }
+    Lexer::relex_from("test".into(), &previous, after, offset, options).unwrap()
+}
+
+#[test]
+fn relexing_an_edited_name_matches_tokenize() {
+    let before = "/** doc */\nfn foo(bar) {\n    return bar + 1;\n}\n";
+    let after = "/** doc */\nfn foo(bar) {\n    return barbaz + 1;\n}\n";
+    let offset = before.rfind("bar").unwrap() + 3;
+    assert_eq!(
+        relex(before, after, offset, &LexerOptions::default()),
+        tokenize("test".into(), after).unwrap()
+    );
+}
+
+#[test]
+fn relexing_an_edit_at_the_start_matches_tokenize() {
+    let before = "fn f() {}";
+    let after = "/** doc */ fn f() {}";
+    assert_eq!(
+        relex(before, after, 0, &LexerOptions::default()),
+        tokenize("test".into(), after).unwrap()
+    );
+}
+
+#[test]
+fn relexing_without_previous_tokens_lexes_everything() {
+    let source = "fn f() { 1 }";
+    assert_eq!(
+        Lexer::relex_from("test".into(), &[], source, 3, &LexerOptions::default()).unwrap(),
+        tokenize("test".into(), source).unwrap()
+    );
+}
+
+#[test]
+fn relexing_uses_the_given_options() {
+    let options = LexerOptions {
+        tab_width: NonZero::new(4).unwrap(),
+        ..LexerOptions::default()
+    };
+    let before = "fn f() {\n\tx;\n\ty;\n}";
+    let after = "fn f() {\n\tx;\n\tyy;\n}";
+    let offset = before.find('y').unwrap() + 1;
+    let relexed = relex(before, after, offset, &options);
+    let mut lexer = Lexer::new_with_options("test".into(), after, &options);
+    for token in relexed {
+        assert_eq!(token, lexer.next_significant_token().unwrap());
+    }
+}
+
+#[test]
+fn highlight_clamps_to_a_char_boundary() {
+    let at = |position| Location {
+        filepath: "test".into(),
+        position,
+        line: NonZero::<usize>::MIN,
+        column: NonZero::<usize>::MIN,
+    };
+    assert_eq!(highlight("é", at(0)), "‹›é");
+    assert_eq!(highlight("é", at(1)), "‹›é");
+    assert_eq!(highlight("é", at(2)), "é‹›");
+    assert_eq!(highlight("é", at(10)), "é‹›");
+    assert_eq!(highlight("", at(1)), "‹›");
+}
+
+fn kinds(source: &str) -> Vec<TokenKind> {
+    tokenize("test".into(), source)
+        .unwrap()
+        .into_iter()
+        .map(|token| token.kind)
+        .collect()
+}
+
+#[test]
+fn backslash_newline_continues_a_string() {
+    assert_eq!(
+        kinds("\"a\\\n   b\""),
+        [TokenKind::String("ab".into()), TokenKind::EOF]
+    );
+    assert_eq!(
+        kinds("\"a \\\n\t\tb\\\n\""),
+        [TokenKind::String("a b".into()), TokenKind::EOF]
+    );
+    assert!(tokenize("test".into(), "'\\\n'").is_err());
+}
+
+#[test]
+fn digit_separators_only_between_digits() {
+    for (source, expected) in [
+        ("1_000", Ok(TokenKind::Integer(1000))),
+        ("0x1_F", Ok(TokenKind::Integer(0x1F))),
+        ("0x_1", Err(2)),
+        ("1__0", Err(2)),
+        ("1_", Err(1)),
+        ("1_.5", Err(1)),
+        // A leading `_` starts a name, not a number
+        ("_1", Ok(TokenKind::Name("_1".into()))),
+    ] {
+        let result = tokenize("test".into(), source);
+        match expected {
+            Ok(kind) => assert_eq!(result.unwrap()[0].kind, kind, "{source}"),
+            Err(position) => {
+                let error = result.unwrap_err();
+                assert!(
+                    matches!(error.kind, LexerErrorKind::MisplacedDigitSeparator),
+                    "{source}"
+                );
+                assert_eq!(error.location.position, position, "{source}");
+            }
+        }
+    }
+}
+
+#[test]
+fn eof_is_located_at_the_end_of_the_source() {
+    for (source, line, column) in [
+        ("", 1, 1),
+        ("fn f() {\n", 2, 1),
+        ("x  \n ", 2, 2),
+        ("x // comment", 1, 13),
+        ("x /* a\nb */", 2, 5),
+    ] {
+        let eof = tokenize("test".into(), source).unwrap().pop().unwrap();
+        assert!(matches!(eof.kind, TokenKind::EOF), "{source:?}");
+        assert_eq!(eof.location.position, source.len(), "{source:?}");
+        assert_eq!(
+            (eof.location.line.get(), eof.location.column.get()),
+            (line, column),
+            "{source:?}"
+        );
+    }
+}
+
+#[test]
+fn token_ranges_and_whitespace_tile_the_source() {
+    let source = "/** Doc */\nfn f(x) { // comment\n\tx + /* inline */ 1;\n}\n";
+    let options = LexerOptions {
+        emit_comments: true,
+        ..LexerOptions::default()
+    };
+    let mut end = 0;
+    for result in Lexer::new_with_options("test".into(), source, &options).token_ranges() {
+        let (_, range) = result.unwrap();
+        assert!(source[end..range.start].trim().is_empty());
+        assert!(range.start < range.end);
+        end = range.end;
+    }
+    assert!(source[end..].trim().is_empty());
+
+    // Without comment tokens the comments are left in the gaps
+    let gaps = Lexer::new("test".into(), source)
+        .token_ranges()
+        .scan(0, |end, result| {
+            let (_, range) = result.unwrap();
+            let gap = &source[*end..range.start];
+            *end = range.end;
+            Some(gap.trim().to_owned())
+        })
+        .filter(|gap| !gap.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(gaps, ["// comment", "/* inline */"]);
+}
+
+#[test]
+fn first_token_is_zero_based_at_the_origin() {
+    let tokens = tokenize("test".into(), "x\n  y").unwrap();
+    assert_eq!(tokens[0].location.zero_based(), (0, 0));
+    assert_eq!(
+        (
+            tokens[0].location.line_usize(),
+            tokens[0].location.column_usize()
+        ),
+        (1, 1)
+    );
+    assert_eq!(tokens[1].location.zero_based(), (1, 2));
+}
+
+#[test]
+fn validating_locations_of_a_normal_file_finds_nothing() {
+    let source = "//! Module\n/** Doc */\nfn main(x) {\n\tlet s = \"é\\u{1F600}\";\n\t/* a\n b */ x + 0xF;\n}\n";
+    for tab_width in [1, 4] {
+        let options = LexerOptions {
+            validate_locations: true,
+            emit_comments: true,
+            tab_width: NonZero::new(tab_width).unwrap(),
+            ..LexerOptions::default()
+        };
+        let mut lexer = Lexer::new_with_options("test".into(), source, &options);
+        while !matches!(lexer.next_raw_token().unwrap().kind, TokenKind::EOF) {}
+    }
+}
+
+#[test]
+fn eat_only_consumes_a_matching_token() {
+    let mut lexer = Lexer::new("test".into(), "x;");
+    assert!(lexer.eat(&TokenKind::Semicolon).unwrap().is_none());
+    assert_eq!(lexer.location().position, 0);
+    let name = lexer.eat(&TokenKind::Name("y".into())).unwrap().unwrap();
+    assert_eq!(name.kind, TokenKind::Name("x".into()));
+    assert!(lexer.eat(&TokenKind::Semicolon).unwrap().is_some());
+    assert!(matches!(lexer.next_token().unwrap().kind, TokenKind::EOF));
+}
+
+#[test]
+fn expect_reports_the_token_it_got() {
+    let mut lexer = Lexer::new("test".into(), "x;");
+    let error = lexer.expect(&TokenKind::Semicolon).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        LexerErrorKind::UnexpectedToken(TokenKind::Name(_))
+    ));
+    assert_eq!(error.location.position, 0);
+    lexer.expect(&TokenKind::Semicolon).unwrap();
+}
+
+#[test]
+fn reserved_words_are_not_names() {
+    let options = LexerOptions {
+        reserved: ["trait".into()].into_iter().collect(),
+        ..LexerOptions::default()
+    };
+    let mut lexer = Lexer::new_with_options("test".into(), "traits trait", &options);
+    assert_eq!(
+        lexer.next_token().unwrap().kind,
+        TokenKind::Name("traits".into())
+    );
+    let error = lexer.next_token().unwrap_err();
+    assert!(matches!(
+        error.kind,
+        LexerErrorKind::ReservedKeyword(name) if name.to_str() == "trait"
+    ));
+    assert_eq!(error.location.position, 7);
+
+    let mut lexer = Lexer::new_with_options("test".into(), "r#trait", &options);
+    assert_eq!(
+        lexer.next_token().unwrap().kind,
+        TokenKind::Name("trait".into())
+    );
+    assert_eq!(
+        tokenize("test".into(), "trait").unwrap()[0].kind,
+        TokenKind::Name("trait".into())
+    );
+}
+
+#[test]
+fn significant_tokens_omit_comments() {
+    let options = LexerOptions {
+        emit_comments: true,
+        ..LexerOptions::default()
+    };
+    let source = "x // comment\n/* block */ y";
+    let kinds = |tokens: Vec<Token>| {
+        tokens
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>()
+    };
+    let raw = Lexer::new_with_options("test".into(), source, &options)
+        .tokens()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        kinds(raw),
+        [
+            TokenKind::Name("x".into()),
+            TokenKind::Comment,
+            TokenKind::Comment,
+            TokenKind::Name("y".into()),
+            TokenKind::EOF,
+        ]
+    );
+    let significant = Lexer::new_with_options("test".into(), source, &options)
+        .significant_tokens()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        kinds(significant),
+        [
+            TokenKind::Name("x".into()),
+            TokenKind::Name("y".into()),
+            TokenKind::EOF,
+        ]
+    );
+    assert!(TokenKind::Comment.is_trivia());
+    assert!(!TokenKind::Semicolon.is_trivia());
+}
+
+#[test]
+fn integers_wider_than_max_int_bits_are_rejected() {
+    for (bits, largest, too_wide) in [(32, "0xFFFF_FFFF", "0x1_0000_0000"), (16, "65535", "65536")]
+    {
+        let options = LexerOptions {
+            max_int_bits: bits,
+            ..LexerOptions::default()
+        };
+        let lex = |source| Lexer::new_with_options("test".into(), source, &options).next_token();
+        assert_eq!(
+            lex(largest).unwrap().kind,
+            TokenKind::Integer((1 << bits) - 1)
+        );
+        assert!(matches!(
+            lex(too_wide).unwrap_err().kind,
+            LexerErrorKind::IntegerTooWide { bits: error_bits } if error_bits == bits
+        ));
+    }
+}
+
+#[test]
+fn lexer_options_take_effect_together() {
+    let options = LexerOptions {
+        tab_width: NonZero::new(8).unwrap(),
+        reserved: ["async".into()].into_iter().collect(),
+        ..LexerOptions::default()
+    };
+    let mut lexer = Lexer::new_with_options("test".into(), "\tx\n\tasync", &options);
+    let x = lexer.next_token().unwrap();
+    assert_eq!(x.location.column.get(), 9);
+    let error = lexer.next_token().unwrap_err();
+    assert!(matches!(error.kind, LexerErrorKind::ReservedKeyword(_)));
+    assert_eq!(
+        (error.location.line.get(), error.location.column.get()),
+        (2, 9)
+    );
+
+    let default = tokenize("test".into(), "\tx").unwrap();
+    assert_eq!(default[0].location.column.get(), 2);
+}
+
+#[test]
+fn raw_names() {
+    assert_eq!(
+        kinds("r#fn r#foo foo r"),
+        [
+            TokenKind::Name("fn".into()),
+            TokenKind::Name("foo".into()),
+            TokenKind::Name("foo".into()),
+            TokenKind::Name("r".into()),
+            TokenKind::EOF,
+        ]
+    );
+    assert_eq!(kinds("fn")[0], TokenKind::Fn);
+}
+
+#[test]
+fn non_breaking_space_is_only_whitespace_by_default() {
+    let source = "x\u{A0}y";
+    assert_eq!(
+        kinds(source),
+        [
+            TokenKind::Name("x".into()),
+            TokenKind::Name("y".into()),
+            TokenKind::EOF,
+        ]
+    );
+    let options = LexerOptions {
+        ascii_whitespace_only: true,
+        ..LexerOptions::default()
+    };
+    let mut lexer = Lexer::new_with_options("test".into(), source, &options);
+    lexer.next_token().unwrap();
+    let error = lexer.next_token().unwrap_err();
+    assert!(matches!(
+        error.kind,
+        LexerErrorKind::UnexpectedChar('\u{A0}')
+    ));
+    assert_eq!(error.location.position, 1);
+}
+
+#[test]
+fn line_comments_are_skipped() {
+    assert_eq!(
+        kinds("1 // hi\n+ 2"),
+        [
+            TokenKind::Integer(1),
+            TokenKind::Plus,
+            TokenKind::Integer(2),
+            TokenKind::EOF,
+        ]
+    );
+    let tokens = tokenize("test".into(), "1 // hi\n+ 2").unwrap();
+    assert_eq!(
+        (
+            tokens[1].location.line.get(),
+            tokens[1].location.column.get()
+        ),
+        (2, 1)
+    );
+    assert_eq!(
+        kinds("x // no newline"),
+        [TokenKind::Name("x".into()), TokenKind::EOF]
+    );
+}
@@ -0,0 +1,37 @@
+use lang::line_index::{LineIndex, LspPosition};
+
+#[test]
+fn emoji_counts_as_two_utf16_code_units() {
+    let source = "fn f() {}\nlet s = \"😀\"; x;\n";
+    let index = LineIndex::new(source);
+    let x = source.find('x').unwrap();
+    // the emoji is 4 bytes but 2 UTF-16 code units
+    assert_eq!(
+        index.to_lsp_position(x),
+        LspPosition {
+            line: 1,
+            character: 14
+        }
+    );
+    assert_eq!(index.offset(1, 14), x);
+    let emoji = source.find('😀').unwrap();
+    assert_eq!(index.to_lsp_position(emoji).character, 9);
+    assert_eq!(index.to_lsp_position(emoji + '😀'.len_utf8()).character, 11);
+}
+
+#[test]
+fn offsets_are_clamped() {
+    let source = "ab😀\ncd";
+    let index = LineIndex::new(source);
+    // in the middle of the surrogate pair
+    assert_eq!(index.offset(0, 3), 2);
+    assert_eq!(index.offset(0, 100), source.find('\n').unwrap());
+    assert_eq!(index.offset(5, 0), source.len());
+    assert_eq!(
+        index.to_lsp_range(0..source.len()).end,
+        LspPosition {
+            line: 1,
+            character: 2
+        }
+    );
+}
@@ -0,0 +1,66 @@
+use lang::{
+    lints::{check_brace_indent, check_dead_functions, check_leading_zeros, WarningKind},
+    parsing::parse,
+};
+
+#[test]
+fn misindented_statement_is_flagged() {
+    let warnings = check_brace_indent("test".into(), "fn f() {\n   x;\n}\n").unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].kind,
+        WarningKind::MisindentedLine {
+            expected: 4,
+            found: 3
+        }
+    );
+    assert_eq!(warnings[0].location.line.get(), 2);
+}
+
+#[test]
+fn correctly_indented_statements_are_not_flagged() {
+    let source = "fn f() {\n    if x {\n        y;\n    }\n    g(a,\n      b);\n}\n";
+    assert!(check_brace_indent("test".into(), source)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn leading_zeros_are_flagged() {
+    let source = "x = 007 + 0 + 0o7 + 70 + 0.5 + 00;";
+    let warnings = check_leading_zeros("test".into(), source).unwrap();
+    let positions = warnings
+        .iter()
+        .map(|warning| {
+            assert_eq!(warning.kind, WarningKind::LeadingZeros);
+            warning.location.position
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        positions,
+        [source.find("007").unwrap(), source.find("00;").unwrap()]
+    );
+}
+
+fn dead_functions(source: &str, entry_points: &[&str]) -> Vec<String> {
+    let asts = parse("test".into(), source).unwrap();
+    let entry_points = entry_points
+        .iter()
+        .map(|&name| name.into())
+        .collect::<Vec<_>>();
+    check_dead_functions(&asts, &entry_points)
+        .into_iter()
+        .map(|warning| warning.kind.to_string())
+        .collect()
+}
+
+#[test]
+fn orphan_function_is_dead() {
+    let source = "fn main() { helper(); }\nfn helper() {}\nfn orphan() {}";
+    assert_eq!(
+        dead_functions(source, &["main"]),
+        ["Function 'orphan' is never called from an entry point"]
+    );
+    assert!(dead_functions(source, &["main", "orphan"]).is_empty());
+    assert_eq!(dead_functions(source, &["orphan"]).len(), 2);
+}
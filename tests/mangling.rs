@@ -0,0 +1,36 @@
+use lang::{interning::InternedStr, mangling::mangle};
+use std::collections::HashSet;
+
+fn mangle_path(path: &[&str]) -> String {
+    let path = path
+        .iter()
+        .map(|&segment| InternedStr::from(segment))
+        .collect::<Vec<_>>();
+    let (&name, scope) = path.split_last().unwrap();
+    mangle(name, scope)
+}
+
+#[test]
+fn top_level_and_nested_names() {
+    assert_eq!(mangle_path(&["main"]), "main");
+    assert_eq!(mangle_path(&["foo", "double"]), "foo__double");
+    assert_eq!(mangle_path(&["my_fn"]), "my_0fn");
+}
+
+#[test]
+fn different_paths_mangle_differently() {
+    let paths: [&[&str]; 7] = [
+        &["a__b"],
+        &["a", "b"],
+        &["a_", "b"],
+        &["a", "_b"],
+        &["a", "b", "c"],
+        &["a", "b__c"],
+        &["a__b", "c"],
+    ];
+    let symbols = paths
+        .iter()
+        .map(|path| mangle_path(path))
+        .collect::<HashSet<_>>();
+    assert_eq!(symbols.len(), paths.len(), "{symbols:?}");
+}
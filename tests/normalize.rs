@@ -0,0 +1,42 @@
+use lang::{ast::Ast, diff::structurally_eq, normalize::normalize, parsing::parse_statement_str};
+
+fn assert_normalizes_to(source: &str, expected: &str) {
+    let normalized = normalize(parse_statement_str("test".into(), source).unwrap());
+    let expected = parse_statement_str("test".into(), expected).unwrap();
+    assert!(
+        structurally_eq(&normalized, &expected),
+        "{source} did not normalize to {expected:?}, got {normalized:?}"
+    );
+}
+
+#[test]
+fn min_integer_literal_is_folded() {
+    assert_normalizes_to("-9223372036854775808;", "-9223372036854775808;");
+    assert_normalizes_to("-9223372036854775808 + 1;", "-9223372036854775807;");
+    assert_normalizes_to("(-9223372036854775807) - 1;", "-9223372036854775808;");
+    assert_normalizes_to("- -9223372036854775808;", "- -9223372036854775808;");
+}
+
+fn normalized(source: &str) -> Ast {
+    normalize(parse_statement_str("test".into(), source).unwrap())
+}
+
+#[test]
+fn commutative_operands_are_ordered() {
+    assert!(structurally_eq(
+        &normalized("a + b;"),
+        &normalized("b + a;")
+    ));
+    assert!(structurally_eq(
+        &normalized("f(x) * -y == 2;"),
+        &normalized("2 == -y * f(x);")
+    ));
+    assert!(structurally_eq(
+        &normalized("(a + b) * c;"),
+        &normalized("c * (b + a);")
+    ));
+    assert!(!structurally_eq(
+        &normalized("a - b;"),
+        &normalized("b - a;")
+    ));
+}
@@ -0,0 +1,748 @@
+use lang::{
+    ast::{AstExpression, AstExpressionKind, AstKind, AstPattern, AstPatternKind},
+    lexer::{tokenize, Lexer, LexerErrorKind, Location, TokenKind},
+    parsing::{
+        parse, parse_expression, parse_expression_str, parse_module, parse_statement_str,
+        parse_with_options, parse_with_tokens, ParseError, ParseErrorKind, ParserOptions,
+    },
+};
+use std::num::NonZero;
+
+#[test]
+fn doc_comment_margins_are_removed() {
+    let asts = parse("test".into(), "/** foo\n * bar */\nfn f() {}").unwrap();
+    let AstKind::Function { doc, .. } = asts[0].kind else {
+        panic!("expected a function");
+    };
+    assert_eq!(doc.map(|doc| doc.to_str()), Some("foo\nbar"));
+}
+
+#[test]
+fn doc_comment_before_a_statement_is_a_comment() {
+    parse("test".into(), "fn main() { /** note */ let x = 1; }").unwrap();
+    parse_statement_str("test".into(), "/** note */ let x = 1;").unwrap();
+}
+
+#[test]
+fn doc_comment_in_an_expression_is_a_comment() {
+    parse("test".into(), "fn main() { f(/** a */ 1); }").unwrap();
+    parse(
+        "test".into(),
+        "fn main() { let x = 1 /** a */ + 2; } /** trailing */",
+    )
+    .unwrap();
+}
+
+#[test]
+fn extern_fn_in_a_block_keeps_its_doc_and_attributes() {
+    let ast = parse_statement_str("test".into(), "/** doc */ #[a] extern fn f();").unwrap();
+    let AstKind::ExternFunction {
+        doc,
+        ref attributes,
+        ..
+    } = ast.kind
+    else {
+        panic!("expected an extern function");
+    };
+    assert_eq!(doc.map(|doc| doc.to_str()), Some("doc"));
+    assert_eq!(attributes.len(), 1);
+}
+
+#[test]
+fn struct_keeps_its_doc_and_attributes() {
+    let asts = parse("test".into(), "/** doc */ #[a] #[b(1)] struct S { x: int }").unwrap();
+    let AstKind::Struct {
+        doc,
+        ref attributes,
+        ..
+    } = asts[0].kind
+    else {
+        panic!("expected a struct");
+    };
+    assert_eq!(doc.map(|doc| doc.to_str()), Some("doc"));
+    assert_eq!(attributes.len(), 2);
+}
+
+#[test]
+fn enum_keeps_its_doc_and_attributes() {
+    let asts = parse("test".into(), "/** doc */ #[a] enum E { A, B(int) }").unwrap();
+    let AstKind::Enum {
+        doc,
+        ref attributes,
+        ref variants,
+        ..
+    } = asts[0].kind
+    else {
+        panic!("expected an enum");
+    };
+    assert_eq!(doc.map(|doc| doc.to_str()), Some("doc"));
+    assert_eq!(attributes.len(), 1);
+    assert_eq!(variants.len(), 2);
+}
+
+#[test]
+fn nested_tuple_pattern_in_let() {
+    let ast = parse_statement_str("test".into(), "let ((a, b), c) = x;").unwrap();
+    let AstKind::Let { ref pattern, .. } = ast.kind else {
+        panic!("expected a let");
+    };
+    let AstPatternKind::Tuple(ref elements) = pattern.kind else {
+        panic!("expected a tuple pattern");
+    };
+    assert!(matches!(
+        elements[..],
+        [
+            AstPattern {
+                kind: AstPatternKind::Tuple(ref inner),
+                ..
+            },
+            AstPattern {
+                kind: AstPatternKind::Let { .. },
+                ..
+            },
+        ] if inner.len() == 2
+    ));
+}
+
+#[test]
+fn refutable_patterns_in_let_are_rejected() {
+    for (source, expected) in [
+        ("let (x @ 1, 2) = y;", "BindingInLet"),
+        ("let (a, 1) = y;", "RefutablePatternInLet"),
+        ("let (true, b) = y;", "RefutablePatternInLet"),
+        ("let ((a, 0), b) = y;", "RefutablePatternInLet"),
+        ("let 1 = y;", "UnexpectedToken"),
+    ] {
+        let error = parse_statement_str("test".into(), source).unwrap_err();
+        assert!(
+            format!("{:?}", error.kind).starts_with(expected),
+            "{source} gave {error}"
+        );
+    }
+    parse_statement_str("test".into(), "let (_, (a,), ()) = y;").unwrap();
+}
+
+#[test]
+fn binding_of_a_tuple_pattern() {
+    let ast = parse_statement_str("test".into(), "match v { x @ (a, b) => x };").unwrap();
+    let AstKind::Expression(AstExpression {
+        kind: AstExpressionKind::Match { ref arms, .. },
+        ..
+    }) = ast.kind
+    else {
+        panic!("expected a match");
+    };
+    let AstPatternKind::Binding { ref pattern, .. } = arms[0].0.kind else {
+        panic!("expected a binding");
+    };
+    assert!(matches!(pattern.kind, AstPatternKind::Tuple(ref elements) if elements.len() == 2));
+
+    parse("test".into(), "fn f(x @ (a, _)) {}").unwrap();
+}
+
+#[test]
+fn adjacent_items_without_whitespace() {
+    let asts = parse("test".into(), "fn a(){}fn b(){}").unwrap();
+    let names = asts
+        .iter()
+        .map(|ast| match ast.kind {
+            AstKind::Function { ref name, .. } => name.kind.to_string(),
+            _ => panic!("expected a function"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+}
+
+#[test]
+fn stray_semicolon_between_items() {
+    let error = parse("test".into(), "fn a(){} ; fn b(){}").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::ExpectedGlobalItem(TokenKind::Semicolon)
+    ));
+    assert_eq!(error.location.position, 9);
+}
+
+#[test]
+fn explanations_are_non_empty_and_distinct() {
+    let location = Location {
+        filepath: "test".into(),
+        position: 0,
+        line: NonZero::<usize>::MIN,
+        column: NonZero::<usize>::MIN,
+    };
+    let lexer_kinds = [
+        LexerErrorKind::UnexpectedToken(TokenKind::Semicolon),
+        LexerErrorKind::UnexpectedChar('$'),
+        LexerErrorKind::ReservedKeyword("async".into()),
+        LexerErrorKind::IntegerTooLarge,
+        LexerErrorKind::IntegerTooWide { bits: 32 },
+        LexerErrorKind::NameTooLong { max: 8 },
+        LexerErrorKind::DigitTooLarge { base: 2 },
+        LexerErrorKind::MissingDigits { base: 16 },
+        LexerErrorKind::UnterminatedString,
+        LexerErrorKind::InvalidCharLiteral,
+        LexerErrorKind::InvalidEscape('q'),
+        LexerErrorKind::InvalidUnicodeEscape,
+        LexerErrorKind::UnterminatedBlockComment,
+        LexerErrorKind::MisplacedDigitSeparator,
+        LexerErrorKind::InconsistentLocation { expected: location },
+    ];
+    let parse_kinds = [
+        ParseErrorKind::UnexpectedToken(TokenKind::Semicolon),
+        ParseErrorKind::ExpectedGlobalItem(TokenKind::Semicolon),
+        ParseErrorKind::ExpectedExpression(TokenKind::Semicolon),
+        ParseErrorKind::ExpectedPattern(TokenKind::Semicolon),
+        ParseErrorKind::ExpectedType(TokenKind::Semicolon),
+        ParseErrorKind::InvalidAssignmentTarget,
+        ParseErrorKind::InclusiveRangeWithoutEnd,
+        ParseErrorKind::TooManyArguments { max: 4 },
+        ParseErrorKind::BindingInLet,
+        ParseErrorKind::RefutablePatternInLet,
+        ParseErrorKind::DuplicateFile("test".into()),
+    ];
+    let explanations = lexer_kinds
+        .iter()
+        .map(LexerErrorKind::explanation)
+        .chain(parse_kinds.iter().map(ParseErrorKind::explanation))
+        .collect::<Vec<_>>();
+    for (i, explanation) in explanations.iter().enumerate() {
+        assert!(!explanation.trim().is_empty(), "explanation {i} is empty");
+        assert!(
+            !explanations[..i].contains(explanation),
+            "explanation {i} is repeated: {explanation}"
+        );
+    }
+    assert_eq!(
+        ParseErrorKind::LexerError(LexerErrorKind::IntegerTooLarge).explanation(),
+        LexerErrorKind::IntegerTooLarge.explanation()
+    );
+}
+
+#[test]
+fn let_of_a_block_like_expression_needs_a_semicolon() {
+    let error =
+        parse_statement_str("test".into(), "let x = if c { a(); } else { b(); }").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::UnexpectedToken(TokenKind::EOF)
+    ));
+    parse_statement_str("test".into(), "let x = if c { a(); } else { b(); };").unwrap();
+    parse_statement_str("test".into(), "let x = match c { _ => 1 };").unwrap();
+    assert!(parse_statement_str("test".into(), "let x = match c { _ => 1 }").is_err());
+}
+
+#[test]
+fn block_like_statement_needs_no_semicolon() {
+    let ast = parse("test".into(), "fn main() { if c { foo(); } bar(); }").unwrap();
+    let AstKind::Function { ref body, .. } = ast[0].kind else {
+        panic!("expected a function");
+    };
+    let AstExpressionKind::Block { ref statements, .. } = body.kind else {
+        panic!("expected a block");
+    };
+    assert_eq!(statements.len(), 2);
+    parse_statement_str("test".into(), "if c { foo(); }").unwrap();
+    parse_statement_str("test".into(), "match x { _ => 1 }").unwrap();
+}
+
+#[test]
+fn return_needs_a_semicolon() {
+    let error = parse_statement_str("test".into(), "return 1").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::UnexpectedToken(TokenKind::EOF)
+    ));
+    assert!(parse(
+        "test".into(),
+        "fn f() { return if c { a(); } else { b(); } }"
+    )
+    .is_err());
+    parse(
+        "test".into(),
+        "fn f() { return if c { a(); } else { b(); }; }",
+    )
+    .unwrap();
+}
+
+#[test]
+fn parse_with_tokens_returns_every_token() {
+    let source = "/** doc */\nfn main() {\n    let x = f(1, 2); // comment\n}\n";
+    let (asts, tokens) = parse_with_tokens("test".into(), source).unwrap();
+    assert_eq!(asts.len(), 1);
+    assert_eq!(tokens.len(), tokenize("test".into(), source).unwrap().len());
+    // `/** doc */ fn main ( ) { let x = f ( 1 , 2 ) ; }` and `EOF`
+    assert_eq!(tokens.len(), 18);
+    assert!(matches!(tokens.last().unwrap().kind, TokenKind::EOF));
+    assert!(parse_with_tokens("test".into(), "fn main() {").is_err());
+}
+
+#[test]
+fn parse_with_tokens_matches_tokenize() {
+    for source in [
+        "",
+        "//! module\nfn f() {}",
+        "fn main() { /** not an item */ let x = P { a: 1 }; if x { f() { y -> y; }; } }",
+        "fn a(){}fn b(){} /** trailing */",
+        "enum E { A(int), B } struct S { x: int, }",
+    ] {
+        let (_, tokens) = parse_with_tokens("test".into(), source).unwrap();
+        assert_eq!(tokens, tokenize("test".into(), source).unwrap(), "{source}");
+    }
+}
+
+#[test]
+fn binding_of_a_range_pattern() {
+    let ast =
+        parse_statement_str("test".into(), "match v { n @ 1..=10 => n, 0..1 => 0 };").unwrap();
+    let AstKind::Expression(AstExpression {
+        kind: AstExpressionKind::Match { ref arms, .. },
+        ..
+    }) = ast.kind
+    else {
+        panic!("expected a match");
+    };
+    let AstPatternKind::Binding { ref pattern, .. } = arms[0].0.kind else {
+        panic!("expected a binding");
+    };
+    assert!(matches!(
+        pattern.kind,
+        AstPatternKind::Range {
+            start: 1,
+            end: 10,
+            inclusive: true
+        }
+    ));
+    assert!(matches!(
+        arms[1].0.kind,
+        AstPatternKind::Range {
+            start: 0,
+            end: 1,
+            inclusive: false
+        }
+    ));
+
+    assert!(parse_statement_str("test".into(), "match v { 1..=x => 0 };").is_err());
+    let error = parse_statement_str("test".into(), "let (1..2, a) = v;").unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::RefutablePatternInLet));
+}
+
+#[test]
+fn generic_function_with_a_where_clause() {
+    let asts = parse("test".into(), "fn f<T>(x: T) where T: Show { }").unwrap();
+    let AstKind::Function {
+        ref generics,
+        ref arguments,
+        ref where_clause,
+        ..
+    } = asts[0].kind
+    else {
+        panic!("expected a function");
+    };
+    let generics = generics
+        .iter()
+        .map(|generic| generic.kind.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(generics, ["T"]);
+    assert_eq!(arguments.len(), 1);
+    assert_eq!(where_clause.len(), 1);
+    assert_eq!(where_clause[0].0.kind.to_string(), "T");
+}
+
+#[test]
+fn where_clause_is_optional() {
+    for source in ["fn f<T>(x: T) { }", "fn f(x) { }", "fn f<>() { }"] {
+        let asts = parse("test".into(), source).unwrap();
+        let AstKind::Function {
+            ref where_clause, ..
+        } = asts[0].kind
+        else {
+            panic!("expected a function");
+        };
+        assert!(where_clause.is_empty(), "{source}");
+    }
+}
+
+#[test]
+fn module_items_are_looked_up_by_filepath() {
+    let module = parse_module([
+        ("a.lang".into(), "//! First\nfn a() {}\nfn b() {}"),
+        ("b.lang".into(), "fn c() {}"),
+    ])
+    .unwrap();
+    let names = |filepath: &str| {
+        module
+            .items_in(filepath.into())
+            .iter()
+            .map(|item| match item.kind {
+                AstKind::Function { ref name, .. } => name.kind.to_string(),
+                _ => panic!("expected a function"),
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("a.lang"), ["a", "b"]);
+    assert_eq!(names("b.lang"), ["c"]);
+    assert!(names("c.lang").is_empty());
+    assert_eq!(module.items.len(), 3);
+    assert_eq!(module.module_doc.unwrap().to_str(), "First");
+    assert!(module
+        .items_in("b.lang".into())
+        .iter()
+        .all(|item| item.location.filepath.to_str() == "b.lang"));
+}
+
+#[test]
+fn duplicate_filepaths_are_rejected() {
+    let errors = parse_module([
+        ("a.lang".into(), "fn a() {}"),
+        ("a.lang".into(), "fn b() {}"),
+    ])
+    .unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [ParseError {
+            kind: ParseErrorKind::DuplicateFile(filepath),
+            location,
+        }] if filepath.to_str() == "a.lang" && location.position == 0
+    ));
+}
+
+fn if_condition(source: &str) -> AstExpression {
+    let AstExpressionKind::If { condition, .. } = std::mem::replace(
+        &mut parse_expression_str("test".into(), source).unwrap().kind,
+        AstExpressionKind::Unit,
+    ) else {
+        panic!("expected an if");
+    };
+    *condition
+}
+
+#[test]
+fn name_before_a_block_in_a_condition_is_not_a_struct_literal() {
+    assert!(matches!(
+        if_condition("if ready { x; }").kind,
+        AstExpressionKind::Name(name) if name.to_str() == "ready"
+    ));
+    assert!(matches!(
+        parse_expression_str("test".into(), "P { x: 1 }")
+            .unwrap()
+            .kind,
+        AstExpressionKind::StructLiteral { .. }
+    ));
+}
+
+#[test]
+fn struct_literals_are_allowed_inside_delimiters_in_a_condition() {
+    for source in [
+        "if f(P { x: 1 }) {}",
+        "if (P { x: 1 }).x {}",
+        "if a[P { x: 1 }] {}",
+        "if [P { x: 1 }] {}",
+    ] {
+        let mut struct_literals = 0;
+        let mut stack = vec![if_condition(source)];
+        while let Some(expression) = stack.pop() {
+            match expression.kind {
+                AstExpressionKind::StructLiteral { .. } => struct_literals += 1,
+                AstExpressionKind::Call { ref arguments, .. } => stack.extend(arguments.clone()),
+                AstExpressionKind::FieldAccess { ref operand, .. } => {
+                    stack.push((**operand).clone())
+                }
+                AstExpressionKind::Index { ref index, .. } => stack.push((**index).clone()),
+                AstExpressionKind::Array { ref elements, .. } => stack.extend(elements.clone()),
+                _ => {}
+            }
+        }
+        assert_eq!(struct_literals, 1, "{source}");
+    }
+}
+
+#[test]
+fn place_expressions() {
+    let options = &ParserOptions {
+        preserve_parens: true,
+        ..ParserOptions::default()
+    };
+    for (source, is_place) in [
+        ("a", true),
+        ("a.b", true),
+        ("a[i]", true),
+        ("(a.b)", true),
+        ("a + b", false),
+        ("f()", false),
+        ("1", false),
+        ("(1)", false),
+    ] {
+        let lexer = &mut Lexer::new("test".into(), source);
+        let expression = parse_expression(lexer, options).unwrap();
+        assert_eq!(expression.is_place_expression(), is_place, "{source}");
+    }
+}
+
+#[test]
+fn assigning_to_a_non_place_is_rejected() {
+    parse_statement_str("test".into(), "a[i].b = 1;").unwrap();
+    for source in ["a + b = 1;", "f() = 1;", "1 = 1;"] {
+        assert!(
+            matches!(
+                parse_statement_str("test".into(), source).unwrap_err().kind,
+                ParseErrorKind::InvalidAssignmentTarget
+            ),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn trailing_closure_is_the_last_argument() {
+    let expression = parse_expression_str("test".into(), "each(xs) { }").unwrap();
+    let AstExpressionKind::Call { ref arguments, .. } = expression.kind else {
+        panic!("expected a call");
+    };
+    assert!(matches!(
+        arguments[..],
+        [
+            AstExpression {
+                kind: AstExpressionKind::Name(_),
+                ..
+            },
+            AstExpression {
+                kind: AstExpressionKind::Closure { .. },
+                ..
+            },
+        ]
+    ));
+}
+
+#[test]
+fn missing_type_annotation_expects_a_type() {
+    let error = parse_statement_str("test".into(), "let x: = 1;").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::ExpectedType(TokenKind::Equals)
+    ));
+    assert_eq!(error.location.position, 7);
+}
+
+#[test]
+fn value_only_constructs_are_not_types() {
+    parse_statement_str("test".into(), "let x: list((int)) = y;").unwrap();
+    for source in [
+        "let x: 1 = y;",
+        "let x: -int = y;",
+        "let x: [int] = y;",
+        "let x: a + b = y;",
+        "let x: a.b = y;",
+    ] {
+        assert!(
+            matches!(
+                parse_statement_str("test".into(), source).unwrap_err().kind,
+                ParseErrorKind::ExpectedType(_) | ParseErrorKind::UnexpectedToken(_)
+            ),
+            "{source}"
+        );
+    }
+    assert!(matches!(
+        parse("test".into(), "fn f() -> 1 {}").unwrap_err().kind,
+        ParseErrorKind::ExpectedType(TokenKind::Integer(1))
+    ));
+}
+
+#[test]
+fn argument_under_the_cursor() {
+    let source = "f(a, bb, c) { x -> }";
+    let call = parse_expression_str("test".into(), source).unwrap();
+    let at = |needle: &str| call.argument_at(source.find(needle).unwrap());
+    assert_eq!(at("a"), Some(0));
+    assert_eq!(at("bb"), Some(1));
+    assert_eq!(call.argument_at(source.find("bb").unwrap() + 1), Some(1));
+    assert_eq!(at(", c"), Some(1));
+    assert_eq!(at("c)"), Some(2));
+    assert_eq!(at("x"), Some(3));
+    assert_eq!(call.argument_at(0), None);
+    assert_eq!(at(" {"), None);
+}
+
+#[test]
+fn parser_options_change_what_parses() {
+    let source = "fn f() { g(a,); h() { x -> }; }";
+    parse("test".into(), source).unwrap();
+    let options = ParserOptions {
+        trailing_commas: false,
+        ..ParserOptions::default()
+    };
+    assert!(matches!(
+        parse_with_options("test".into(), source, &options)
+            .unwrap_err()
+            .kind,
+        ParseErrorKind::UnexpectedToken(TokenKind::CloseParenthesis)
+    ));
+    let options = ParserOptions {
+        trailing_closures: false,
+        ..ParserOptions::default()
+    };
+    assert!(matches!(
+        parse_with_options("test".into(), source, &options)
+            .unwrap_err()
+            .kind,
+        ParseErrorKind::UnexpectedToken(TokenKind::OpenBrace)
+    ));
+}
+
+#[test]
+fn both_paren_layers_are_preserved() {
+    let options = ParserOptions {
+        preserve_parens: true,
+        ..ParserOptions::default()
+    };
+    let mut depth = 0;
+    let mut expression =
+        &parse_expression(&mut Lexer::new("test".into(), "((1 + 2))"), &options).unwrap();
+    while let AstExpressionKind::Paren { ref inner, .. } = expression.kind {
+        depth += 1;
+        expression = inner;
+    }
+    assert_eq!(depth, 2);
+    assert!(matches!(expression.kind, AstExpressionKind::Binary { .. }));
+
+    let expression = parse_expression_str("test".into(), "((1 + 2))").unwrap();
+    assert!(matches!(expression.kind, AstExpressionKind::Binary { .. }));
+}
+
+#[test]
+fn single_statements() {
+    assert!(matches!(
+        parse_statement_str("test".into(), "let x = 1;")
+            .unwrap()
+            .kind,
+        AstKind::Let { .. }
+    ));
+    // there is no check for a `return` outside of a function, so it parses like any other statement
+    assert!(matches!(
+        parse_statement_str("test".into(), "return 1;")
+            .unwrap()
+            .kind,
+        AstKind::Return { .. }
+    ));
+    assert!(matches!(
+        parse_statement_str("test".into(), "x + 1;").unwrap().kind,
+        AstKind::Expression(AstExpression {
+            kind: AstExpressionKind::Binary { .. },
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse_statement_str("test".into(), "let x = 1; y;")
+            .unwrap_err()
+            .kind,
+        ParseErrorKind::UnexpectedToken(TokenKind::Name(_))
+    ));
+}
+
+#[test]
+fn array_literals() {
+    assert!(matches!(
+        parse_expression_str("test".into(), "[]").unwrap().kind,
+        AstExpressionKind::Array { ref elements, .. } if elements.is_empty()
+    ));
+    assert!(matches!(
+        parse_expression_str("test".into(), "[1, 2, 3,]").unwrap().kind,
+        AstExpressionKind::Array { ref elements, .. } if elements.len() == 3
+    ));
+    assert!(matches!(
+        parse_expression_str("test".into(), "[[]; n]").unwrap().kind,
+        AstExpressionKind::ArrayRepeat { ref value, .. }
+            if matches!(value.kind, AstExpressionKind::Array { .. })
+    ));
+}
+
+#[test]
+fn lists_are_limited_to_max_arguments() {
+    let options = ParserOptions {
+        max_arguments: 3,
+        ..ParserOptions::default()
+    };
+    for (under, over) in [
+        ("f(a, b, c);", "f(a, b, c, d);"),
+        ("f(a, b) { x -> };", "f(a, b, c) { x -> };"),
+        ("[a, b, c];", "[a, b, c, d];"),
+        ("P { a: 1, b: 2, c: 3 };", "P { a: 1, b: 2, c: 3, d: 4 };"),
+        ("fn g(a, b, c) {}", "fn g(a, b, c, d) {}"),
+    ] {
+        let wrap = |statement: &str| format!("fn main() {{ {statement} }}");
+        parse_with_options("test".into(), &wrap(under), &options).unwrap();
+        let error = parse_with_options("test".into(), &wrap(over), &options).unwrap_err();
+        assert!(
+            matches!(error.kind, ParseErrorKind::TooManyArguments { max: 3 }),
+            "{over} gave {error}"
+        );
+    }
+}
+
+#[test]
+fn indexing_chains_with_calls_left_to_right() {
+    let expression = parse_expression_str("test".into(), "a[0](x)[1]").unwrap();
+    let AstExpressionKind::Index {
+        ref operand,
+        ref index,
+        ..
+    } = expression.kind
+    else {
+        panic!("expected an index");
+    };
+    assert!(matches!(index.kind, AstExpressionKind::Integer(1)));
+    let AstExpressionKind::Call { ref operand, .. } = operand.kind else {
+        panic!("expected a call");
+    };
+    assert!(matches!(
+        operand.kind,
+        AstExpressionKind::Index { ref index, .. }
+            if matches!(index.kind, AstExpressionKind::Integer(0))
+    ));
+}
+
+#[test]
+fn match_is_an_expression() {
+    let ast =
+        parse_statement_str("test".into(), "let x = match v { 0 => a, _ => f(b), };").unwrap();
+    let AstKind::Let { ref value, .. } = ast.kind else {
+        panic!("expected a let");
+    };
+    let AstExpressionKind::Match {
+        ref scrutinee,
+        ref arms,
+        ..
+    } = value.kind
+    else {
+        panic!("expected a match");
+    };
+    assert!(matches!(scrutinee.kind, AstExpressionKind::Name(_)));
+    assert_eq!(arms.len(), 2);
+    assert!(matches!(arms[1].1.kind, AstExpressionKind::Call { .. }));
+}
+
+#[test]
+fn wildcard_and_literal_patterns() {
+    let expression =
+        parse_expression_str("test".into(), "match x { 0 => a, true => b, _ => c }").unwrap();
+    let AstExpressionKind::Match { ref arms, .. } = expression.kind else {
+        panic!("expected a match");
+    };
+    let patterns = arms
+        .iter()
+        .map(|(pattern, _)| &pattern.kind)
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        patterns[..],
+        [
+            AstPatternKind::Integer(0),
+            AstPatternKind::Boolean(true),
+            AstPatternKind::Wildcard,
+        ]
+    ));
+    // `_` followed by more characters is still a name
+    assert!(matches!(
+        parse_expression_str("test".into(), "_x").unwrap().kind,
+        AstExpressionKind::Name(_)
+    ));
+}
@@ -0,0 +1,296 @@
+use lang::{
+    ast::AstExpressionKind,
+    lexer::{tokenize, Lexer, TokenKind},
+    parsing::{parse, parse_expression, parse_expression_str, parse_statement_str, ParserOptions},
+    pretty_printing::{
+        measure_width, pretty_print_ast, pretty_print_ast_expression, IntegerBase, PrettyPrinter,
+    },
+    visitor::Visitor,
+};
+
+#[test]
+fn long_left_associative_sum() {
+    let terms = 50_000;
+    let source = vec!["1"; terms].join(" + ");
+    let expression = parse_expression_str("test".into(), &source).unwrap();
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    let expected = "(".repeat(terms - 1) + "1" + &" + 1)".repeat(terms - 1);
+    assert!(String::from_utf8(printed).unwrap() == expected);
+}
+
+/// The sample from `src/bin/main.rs`
+const SAMPLE: &str = "
+fn foo(param) -> int {
+    let x = 1 + 2 * 3;
+    let y = x / 2;
+    fn double(x) {
+        return x * 2;
+    }
+    let z = double(y);
+    return 10 / param - (z - 5);
+}
+";
+/// What the printer output for `SAMPLE` before it was built on `Visitor`
+const SAMPLE_PRINTED: &str = "fn foo(param) -> int {
+    let x = (1 + (2 * 3));
+    let y = (x / 2);
+    fn double(x) {
+        return (x * 2);
+    }
+    let z = double(y);
+    return ((10 / param) - (z - 5));
+}
+";
+
+#[test]
+fn sample_prints_byte_identically() {
+    let asts = parse("test.lang".into(), SAMPLE).unwrap();
+    let mut printed = vec![];
+    for ast in &asts {
+        pretty_print_ast(ast, 0, &mut printed).unwrap();
+    }
+    assert_eq!(String::from_utf8(printed).unwrap(), SAMPLE_PRINTED);
+}
+
+#[test]
+fn measured_width_matches_the_printed_width() {
+    for source in [
+        "x",
+        "r#fn",
+        "1234567",
+        "-a + b * 3",
+        "f(a, b, 1 == 2)",
+        "a.b.c[i]",
+        "\"a\\nb\" == 'é'",
+        "[1, 2, 3]",
+        "[0; 16]",
+        "a..=b",
+        "Point { x: 1, y: 2 }",
+        "if a { b(); } else { c(); }",
+        "match x { 0 => a, _ => b }",
+        "f() { x, y -> x + y; }",
+        "f(a) { b(); }",
+    ] {
+        let expression = parse_expression_str("test".into(), source).unwrap();
+        let mut printed = vec![];
+        pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+        let printed = String::from_utf8(printed).unwrap();
+        let first_line = printed.lines().next().unwrap_or_default();
+        assert_eq!(
+            measure_width(&expression),
+            first_line.chars().count(),
+            "{source} printed as {printed:?}"
+        );
+    }
+}
+
+#[test]
+fn range_patterns_print_as_written() {
+    let source = "match n { n @ 1..=10 => n, 0..1 => 0, _ => 2 }";
+    let expression = parse_expression_str("test".into(), source).unwrap();
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    let printed = String::from_utf8(printed).unwrap();
+    assert!(printed.contains("n @ 1..=10 => n"), "{printed}");
+    assert!(printed.contains("0..1 => 0"), "{printed}");
+}
+
+#[test]
+fn generics_and_where_clause_are_printed() {
+    let asts = parse("test".into(), "fn f<T, U>(x: T) where T: Show { }").unwrap();
+    let mut printed = vec![];
+    pretty_print_ast(&asts[0], 0, &mut printed).unwrap();
+    let printed = String::from_utf8(printed).unwrap();
+    assert!(
+        printed.starts_with("fn f<T, U>(x: T) where T: Show {"),
+        "{printed}"
+    );
+}
+
+/// The printed float and the width `measure_width` gives it
+fn print_float(value: f64) -> (String, usize) {
+    let mut expression = parse_expression_str("test".into(), "0.0").unwrap();
+    expression.kind = AstExpressionKind::Float(value);
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    (
+        String::from_utf8(printed).unwrap(),
+        measure_width(&expression),
+    )
+}
+
+#[test]
+fn floats_print_in_the_lexers_syntax() {
+    for value in [1.5, 1e20, 1e-7, f64::MAX, f64::MIN_POSITIVE, 5e-324, 0.0] {
+        let (printed, width) = print_float(value);
+        assert!(!printed.contains('e'), "{printed}");
+        assert_eq!(printed.len(), width);
+        assert_eq!(
+            tokenize("test".into(), &printed).unwrap()[0].kind,
+            TokenKind::Float(value)
+        );
+    }
+}
+
+#[test]
+fn non_finite_floats_print_as_divisions() {
+    for (value, expected) in [
+        (f64::INFINITY, "(1.0 / 0.0)"),
+        (f64::NEG_INFINITY, "(-1.0 / 0.0)"),
+        (f64::NAN, "(0.0 / 0.0)"),
+    ] {
+        let (printed, width) = print_float(value);
+        assert_eq!(printed, expected);
+        assert_eq!(printed.len(), width);
+        parse_expression_str("test".into(), &printed).unwrap();
+    }
+}
+
+#[test]
+fn trailing_closures_print_after_the_parentheses() {
+    let expression = parse_expression_str("test".into(), "each(xs) { item -> f(item); }").unwrap();
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    assert_eq!(
+        String::from_utf8(printed).unwrap(),
+        "each(xs) { item ->\n    f(item);\n}"
+    );
+}
+
+#[test]
+fn preserved_parens_print_verbatim() {
+    let options = ParserOptions {
+        preserve_parens: true,
+        ..ParserOptions::default()
+    };
+    let expression =
+        parse_expression(&mut Lexer::new("test".into(), "((1 + 2)) * (x)"), &options).unwrap();
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    assert_eq!(String::from_utf8(printed).unwrap(), "(((1 + 2)) * (x))");
+}
+
+fn print_with_max_width(source: &str, max_width: usize) -> String {
+    let expression = parse_expression_str("test".into(), source).unwrap();
+    let mut printed = vec![];
+    PrettyPrinter::new(&mut printed, 0)
+        .with_max_width(max_width)
+        .visit_expression(&expression)
+        .unwrap();
+    String::from_utf8(printed).unwrap()
+}
+
+#[test]
+fn short_call_stays_inline() {
+    assert_eq!(print_with_max_width("f(a, b)", 20), "f(a, b)");
+}
+
+#[test]
+fn long_call_breaks_one_argument_per_line() {
+    assert_eq!(
+        print_with_max_width("function(first_argument, second_argument)", 20),
+        "function(\n    first_argument,\n    second_argument,\n)"
+    );
+}
+
+#[test]
+fn float_literals_round_trip() {
+    let ast = parse_statement_str("test".into(), "let x = 2.5 + 1.0;").unwrap();
+    let mut printed = vec![];
+    pretty_print_ast(&ast, 0, &mut printed).unwrap();
+    assert_eq!(
+        String::from_utf8(printed).unwrap(),
+        "let x = (2.5 + 1.0);\n"
+    );
+}
+
+fn print_integer(value: &str, base: IntegerBase, group_digits: bool) -> String {
+    let expression = parse_expression_str("test".into(), value).unwrap();
+    let mut printed = vec![];
+    PrettyPrinter::new(&mut printed, 0)
+        .with_integer_base(base)
+        .with_group_digits(group_digits)
+        .visit_expression(&expression)
+        .unwrap();
+    String::from_utf8(printed).unwrap()
+}
+
+#[test]
+fn hexadecimal_and_binary_digits_are_grouped() {
+    assert_eq!(print_integer("255", IntegerBase::Hexadecimal, true), "0xFF");
+    assert_eq!(
+        print_integer("65535", IntegerBase::Hexadecimal, true),
+        "0xFF_FF"
+    );
+    assert_eq!(
+        print_integer("4095", IntegerBase::Hexadecimal, true),
+        "0xF_FF"
+    );
+    assert_eq!(
+        print_integer("513", IntegerBase::Binary, true),
+        "0b10_00000001"
+    );
+    assert_eq!(
+        print_integer("1000000", IntegerBase::Decimal, true),
+        "1000000"
+    );
+}
+
+#[test]
+fn digits_are_not_grouped_when_disabled() {
+    assert_eq!(
+        print_integer("65535", IntegerBase::Hexadecimal, false),
+        "0xFFFF"
+    );
+    assert_eq!(
+        print_integer("513", IntegerBase::Binary, false),
+        "0b1000000001"
+    );
+}
+
+#[test]
+fn arrays_print_as_written() {
+    for (source, expected) in [
+        ("[]", "[]"),
+        ("[1, 2, 3,]", "[1, 2, 3]"),
+        ("[0; 4]", "[0; 4]"),
+        ("[[]; n]", "[[]; n]"),
+    ] {
+        let expression = parse_expression_str("test".into(), source).unwrap();
+        let mut printed = vec![];
+        pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+        assert_eq!(String::from_utf8(printed).unwrap(), expected);
+    }
+}
+
+#[test]
+fn indexing_prints_after_its_operand() {
+    let expression = parse_expression_str("test".into(), "a[0](x)[i + 1]").unwrap();
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    assert_eq!(String::from_utf8(printed).unwrap(), "a[0](x)[(i + 1)]");
+}
+
+#[test]
+fn match_arms_print_on_their_own_lines() {
+    let ast = parse_statement_str("test".into(), "let x = match v { 0 => a, _ => f(b) };").unwrap();
+    let mut printed = vec![];
+    pretty_print_ast(&ast, 0, &mut printed).unwrap();
+    assert_eq!(
+        String::from_utf8(printed).unwrap(),
+        "let x = match v {\n    0 => a,\n    _ => f(b),\n};\n"
+    );
+}
+
+#[test]
+fn wildcard_and_literal_patterns_print_as_written() {
+    let expression =
+        parse_expression_str("test".into(), "match x { 0 => a, false => b, _ => c }").unwrap();
+    let mut printed = vec![];
+    pretty_print_ast_expression(&expression, 0, &mut printed).unwrap();
+    assert_eq!(
+        String::from_utf8(printed).unwrap(),
+        "match x {\n    0 => a,\n    false => b,\n    _ => c,\n}"
+    );
+}
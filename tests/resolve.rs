@@ -0,0 +1,25 @@
+use lang::{
+    parsing::parse,
+    resolve::{check_global_names, global_declarations, DeclId, ResolveErrorKind},
+};
+
+#[test]
+fn duplicate_functions_are_rejected() {
+    let items = parse("test".into(), "fn f() {}\nfn g() {}\nfn f() {}").unwrap();
+    let errors = check_global_names(&items).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    let ResolveErrorKind::DuplicateDefinition { name, first } = errors[0].kind;
+    assert_eq!(name.to_str(), "f");
+    assert_eq!(first.line.get(), 1);
+    assert_eq!(errors[0].location.line.get(), 3);
+    assert_eq!(global_declarations(&items)[&name], DeclId(0));
+}
+
+/// Functions and types share one namespace
+#[test]
+fn function_and_struct_with_the_same_name_are_rejected() {
+    let items = parse("test".into(), "fn Point() {}\nstruct Point {}").unwrap();
+    assert!(check_global_names(&items).is_err());
+    let items = parse("test".into(), "fn point() {}\nstruct Point {}").unwrap();
+    assert!(check_global_names(&items).is_ok());
+}
@@ -0,0 +1,48 @@
+use lang::{diff::ast_diff, parsing::parse, pretty_printing::pretty_print_ast};
+use std::{fs, path::Path};
+
+/// Every file in `tests/corpus` has to parse, and parse again to the same tree after being pretty printed
+#[test]
+fn corpus_round_trips() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut paths = fs::read_dir(&corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "the corpus should not be empty");
+
+    for path in paths {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let source = fs::read_to_string(&path).unwrap();
+        let asts = parse(name.into(), &source)
+            .unwrap_or_else(|error| panic!("{name} did not parse: {error}"));
+
+        let mut printed = vec![];
+        for ast in &asts {
+            pretty_print_ast(ast, 0, &mut printed).unwrap();
+        }
+        let printed = String::from_utf8(printed).unwrap();
+        let reparsed = parse(name.into(), &printed).unwrap_or_else(|error| {
+            panic!("{name} did not parse after printing: {error}\n{printed}")
+        });
+
+        assert_eq!(
+            asts.len(),
+            reparsed.len(),
+            "{name} has a different number of items after printing"
+        );
+        for (ast, reparsed) in asts.iter().zip(&reparsed) {
+            let diffs = ast_diff(ast, reparsed);
+            assert!(
+                diffs.is_empty(),
+                "{name} changed after printing:\n{}\n{printed}",
+                diffs
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+    }
+}